@@ -0,0 +1,79 @@
+//! Times the physics of a few thousand particles, to weigh the cost of the `double-precision`
+//! feature:
+//!
+//! ```sh
+//! cargo bench --bench precision
+//! cargo bench --bench precision --features double-precision
+//! ```
+
+use std::time::{Duration, Instant};
+
+use bevy::{prelude::*, time::TimePlugin};
+use particle_life::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+const PARTICLES_PER_COLOR: usize = 1000;
+const WARMUP_STEPS: usize = 20;
+const STEPS: usize = 200;
+
+fn main() {
+    let bounds = WorldBounds::default();
+    let mut rng = StdRng::seed_from_u64(0);
+    let colors = [Color::RED, Color::GREEN, Color::BLUE, Color::YELLOW];
+    let initial_particles = (0..colors.len())
+        .fold(ParticleSet::new(bounds), |particles, color| {
+            particles.rect(
+                Rect::new(-1.0, -1.0, 1.0, 1.0),
+                PARTICLES_PER_COLOR,
+                ColorId(color),
+                &mut rng,
+            )
+        })
+        .into_particles();
+    let color_attractions = (0..colors.len())
+        .map(|receiver| {
+            (0..colors.len())
+                .map(|source| Attraction(if receiver == source { 0.3 } else { -0.05 }))
+                .collect()
+        })
+        .collect();
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins.build().disable::<TimePlugin>())
+        .init_resource::<Time>()
+        .add_plugin(ParticleLifePlugin {
+            initial_particles,
+            colors: colors.to_vec(),
+            color_attractions: ColorAttractions(color_attractions),
+            attraction_radius: AttractionRadius {
+                rmin: 0.02,
+                rmax: 0.08,
+            },
+            seed: Some(0),
+            headless: true,
+            ..Default::default()
+        });
+
+    advance_steps(&mut app, 0.01, WARMUP_STEPS);
+    let mut times: Vec<Duration> = (0..STEPS)
+        .map(|_| {
+            let start = Instant::now();
+            advance(&mut app, 0.01);
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+
+    let precision = if cfg!(feature = "double-precision") {
+        "double"
+    } else {
+        "single"
+    };
+    let total: Duration = times.iter().sum();
+    println!(
+        "{} particles, {precision} precision: {:?} per step on average, {:?} median",
+        colors.len() * PARTICLES_PER_COLOR,
+        total / STEPS as u32,
+        times[STEPS / 2],
+    );
+}
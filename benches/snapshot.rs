@@ -0,0 +1,76 @@
+//! Times saving and loading a snapshot of 50k particles in the binary and text formats, and
+//! compares their sizes:
+//!
+//! ```sh
+//! cargo bench --bench snapshot
+//! ```
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use particle_life::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const PARTICLES: usize = 50_000;
+const COLORS: usize = 4;
+const RUNS: u32 = 10;
+
+fn main() {
+    let bounds = WorldBounds::default();
+    let mut rng = StdRng::seed_from_u64(0);
+    let particles = ParticleSet::new(bounds)
+        .rect(
+            Rect::new(-1.0, -1.0, 1.0, 1.0),
+            PARTICLES,
+            ColorId(0),
+            &mut rng,
+        )
+        .into_particles()
+        .into_iter()
+        .map(|particle| Particle {
+            velocity: Velocity(RealVec2::new(
+                rng.gen_range(-0.1..0.1),
+                rng.gen_range(-0.1..0.1),
+            )),
+            color: ColorId(rng.gen_range(0..COLORS)),
+            ..particle
+        })
+        .collect();
+    let snapshot = SimulationSnapshot {
+        bounds,
+        colors: vec![Color::RED, Color::GREEN, Color::BLUE, Color::YELLOW],
+        color_attractions: ColorAttractions(vec![vec![Attraction(0.1); COLORS]; COLORS]),
+        particles,
+        ..Default::default()
+    };
+
+    let directory = std::env::temp_dir();
+    let binary_path = directory.join("particle_life_snapshot_bench.bin");
+    let text_path = directory.join("particle_life_snapshot_bench.txt");
+
+    let binary_save = time(|| snapshot.save_binary(&binary_path).unwrap());
+    let binary_load = time(|| {
+        SimulationSnapshot::load_binary(&binary_path).unwrap();
+    });
+    let text_save = time(|| snapshot.save_text(&text_path).unwrap());
+    let text_load = time(|| {
+        SimulationSnapshot::load_text(&text_path).unwrap();
+    });
+    let binary_size = std::fs::metadata(&binary_path).unwrap().len();
+    let text_size = std::fs::metadata(&text_path).unwrap().len();
+    std::fs::remove_file(binary_path).unwrap();
+    std::fs::remove_file(text_path).unwrap();
+
+    println!("{PARTICLES} particles, averaged over {RUNS} runs:");
+    println!("binary: {binary_size} bytes, saved in {binary_save:?}, loaded in {binary_load:?}");
+    println!("text: {text_size} bytes, saved in {text_save:?}, loaded in {text_load:?}");
+}
+
+/// The average time `f` takes to run.
+fn time(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        f();
+    }
+    start.elapsed() / RUNS
+}
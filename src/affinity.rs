@@ -0,0 +1,109 @@
+//! Measuring which colors actually cluster together, to compare the outcome of a simulation with
+//! the attractions it was given.
+
+use std::{fmt::Write as _, io, path::Path};
+
+use crate::{grid::NeighborGrid, precision::real, toroidal_dist, Particle, Position, WorldBounds};
+
+/// Returns the empirical affinity matrix of `particles`: the entry `[i][j]` is the average number
+/// of particles of the `j`th color within `radius` of a particle of the `i`th color, among
+/// `colors` colors.
+///
+/// The particles of colors without particles have no neighbors, and particles with colors out of
+/// the `colors` colors are ignored. Passing [`AttractionRadius::rmax`](crate::AttractionRadius)
+/// as `radius` counts the neighbors each particle feels.
+pub fn affinity_graph(
+    particles: &[Particle],
+    colors: usize,
+    radius: f32,
+    bounds: &WorldBounds,
+) -> Vec<Vec<f32>> {
+    let particles: Vec<_> = particles
+        .iter()
+        .filter(|particle| particle.color.0 < colors)
+        .collect();
+    let positions: Vec<Position> = particles.iter().map(|particle| particle.position).collect();
+    let mut grid = NeighborGrid::default();
+    grid.rebuild(&positions, radius, bounds);
+
+    let mut neighbors = vec![vec![0_u32; colors]; colors];
+    grid.for_each_pair(|a, b| {
+        if toroidal_dist(positions[a].0, positions[b].0, bounds) <= real(radius) {
+            let (color_a, color_b) = (particles[a].color.0, particles[b].color.0);
+            neighbors[color_a][color_b] += 1;
+            neighbors[color_b][color_a] += 1;
+        }
+    });
+
+    let mut counts = vec![0_u32; colors];
+    for particle in &particles {
+        counts[particle.color.0] += 1;
+    }
+    neighbors
+        .into_iter()
+        .zip(counts)
+        .map(|(row, count)| {
+            row.into_iter()
+                .map(|neighbors| match count {
+                    0 => 0.0,
+                    count => neighbors as f32 / count as f32,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Writes an affinity matrix from [`affinity_graph`] as CSV, one row of the matrix per line.
+pub fn export_affinity_csv(path: impl AsRef<Path>, affinities: &[Vec<f32>]) -> io::Result<()> {
+    let mut csv = String::new();
+    for row in affinities {
+        for (j, affinity) in row.iter().enumerate() {
+            if j > 0 {
+                csv.push(',');
+            }
+            write!(csv, "{affinity}").unwrap();
+        }
+        csv.push('\n');
+    }
+    std::fs::write(path, csv)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+    use crate::{precision::real_vec2, ColorId, Velocity};
+
+    #[test]
+    fn affinities_count_the_neighbors_by_color() {
+        let particle = |x: f32, y: f32, color: usize| Particle {
+            position: Position(real_vec2(Vec2::new(x, y))),
+            velocity: Velocity::default(),
+            color: ColorId(color),
+        };
+        let particles = [
+            // A pair of the first color
+            particle(0.0, 0.0, 0),
+            particle(0.05, 0.0, 0),
+            // A pair of the second color
+            particle(0.5, 0.5, 1),
+            particle(0.53, 0.5, 1),
+            // A mixed pair across the edge of the world
+            particle(-0.98, 0.0, 1),
+            particle(0.98, 0.0, 0),
+            // Out of the palette
+            particle(0.02, 0.0, 5),
+        ];
+
+        let affinities = affinity_graph(&particles, 3, 0.1, &WorldBounds::default());
+        assert_eq!(
+            affinities,
+            vec![
+                vec![2.0 / 3.0, 1.0 / 3.0, 0.0],
+                vec![1.0 / 3.0, 2.0 / 3.0, 0.0],
+                vec![0.0; 3],
+            ]
+        );
+    }
+}
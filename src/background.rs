@@ -0,0 +1,77 @@
+use bevy::{
+    diagnostic::Diagnostics,
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task},
+};
+use futures_lite::future;
+
+use crate::{
+    grid::NeighborGrid, perf::PhysicsTimer, precision::real, ColorId, ForceComputation,
+    ForceSettings, Position, RealVec2, ReverseTime, SimulationId, SimulationTime, Velocity,
+};
+
+/// Forces being computed in the background.
+#[derive(Default)]
+pub(crate) struct PendingForces {
+    /// The accelerations of `entities`, in the same order.
+    task: Option<Task<Vec<RealVec2>>>,
+    entities: Vec<Entity>,
+    /// Simulated time since the snapshot the task works on was taken.
+    elapsed: f32,
+}
+
+pub(crate) fn update_velocity_in_background(
+    simulation_time: Res<SimulationTime>,
+    reverse_time: Res<ReverseTime>,
+    force_computation: Res<ForceComputation>,
+    force_settings: ForceSettings,
+    diagnostics: Option<ResMut<Diagnostics>>,
+    mut pending: Local<PendingForces>,
+    mut query: Query<(&mut Velocity, &Position, &ColorId, &SimulationId, Entity)>,
+) {
+    if *force_computation != ForceComputation::Background {
+        // Cancel any computation left over from before switching modes
+        pending.task = None;
+        return;
+    }
+    pending.elapsed += simulation_time.delta;
+    let timer = PhysicsTimer::start();
+
+    if let Some(task) = &mut pending.task {
+        let Some(accelerations) = future::block_on(future::poll_once(task)) else {
+            timer.stop(diagnostics);
+            return;
+        };
+
+        let delta = reverse_time.direction() * real(pending.elapsed);
+        for (&entity, acceleration) in pending.entities.iter().zip(accelerations) {
+            // The particle may have been despawned since the snapshot was taken
+            if let Ok((mut velocity, ..)) = query.get_mut(entity) {
+                velocity.0 += delta * acceleration;
+            }
+        }
+    }
+
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    let mut simulations = Vec::new();
+    pending.entities.clear();
+    for (_, &position, &color, &simulation, entity) in &query {
+        positions.push(position);
+        colors.push(color);
+        simulations.push(simulation);
+        pending.entities.push(entity);
+    }
+
+    let force_models = force_settings.force_models();
+    pending.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+        force_models.accelerations(
+            &positions,
+            &colors,
+            &simulations,
+            &mut NeighborGrid::default(),
+        )
+    }));
+    pending.elapsed = 0.0;
+    timer.stop(diagnostics);
+}
@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+
+use crate::{
+    grid::NeighborGrid,
+    lines::Lines,
+    precision::{real, single, single_vec2},
+    toroidal_delta, ColorId, ForceSettings, Position, SimulationId,
+};
+
+/// Draws thin lines between nearby particles that attract each other, like chemical bonds.
+///
+/// A bond is drawn between two particles closer than `max_distance` when both are attracted by
+/// the other. The opacity of each bond is proportional to its strength relative to the strongest
+/// bond of the frame. Bonds whose shortest path crosses the edge of the world are not drawn, and
+/// neither are the bonds of simulations other than [`SimulationId::MAIN`].
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct BondRendering {
+    pub enabled: bool,
+    pub max_distance: f32,
+}
+
+impl Default for BondRendering {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_distance: 0.1,
+        }
+    }
+}
+
+pub(crate) fn draw_bonds(
+    bond_rendering: Res<BondRendering>,
+    force_settings: ForceSettings,
+    mut lines: ResMut<Lines>,
+    mut grid: Local<NeighborGrid>,
+    query: Query<(&Position, &ColorId, &SimulationId)>,
+) {
+    if !bond_rendering.enabled {
+        return;
+    }
+    let force_models = force_settings.force_models();
+    let force_model = force_models.get(SimulationId::MAIN);
+
+    let (positions, colors): (Vec<_>, Vec<_>) = query
+        .iter()
+        .filter(|(.., &simulation)| simulation == SimulationId::MAIN)
+        .map(|(&position, &color, _)| (position, color))
+        .unzip();
+    grid.rebuild(
+        &positions,
+        bond_rendering.max_distance,
+        &force_models.bounds,
+    );
+
+    let mut bonds = Vec::new();
+    grid.for_each_pair(|a, b| {
+        let (position_a, position_b) = (&positions[a], &positions[b]);
+        let difference = toroidal_delta(position_a.0, position_b.0, &force_models.bounds);
+        // Don't draw lines across the seam
+        if difference != position_b.0 - position_a.0 {
+            return;
+        }
+
+        if difference.length() >= real(bond_rendering.max_distance) {
+            return;
+        }
+
+        let distance = force_models.anisotropy.distance(difference);
+        let (attraction_a_by_b, attraction_b_by_a) =
+            force_model.attraction_factor(distance, colors[a], colors[b]);
+        if attraction_a_by_b > 0.0 && attraction_b_by_a > 0.0 {
+            let strength = single((attraction_a_by_b + attraction_b_by_a) / 2.0);
+            bonds.push((
+                single_vec2(position_a.0),
+                single_vec2(position_b.0),
+                strength,
+            ));
+        }
+    });
+
+    let max_strength = bonds
+        .iter()
+        .map(|&(_, _, strength)| strength)
+        .fold(0.0, f32::max);
+    for (start, end, strength) in bonds {
+        lines.line(
+            start,
+            end,
+            Color::rgba(1.0, 1.0, 1.0, strength / max_strength),
+        );
+    }
+}
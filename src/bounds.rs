@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+
+use crate::{
+    precision::{real, real_vec2, single_vec2},
+    Real, RealVec2,
+};
+
+/// How the edges of the world are glued together.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Topology {
+    /// Each edge wraps around to the opposite one.
+    #[default]
+    Torus,
+    /// The left and right edges wrap around to each other, and particles bounce off the bottom
+    /// and top edges.
+    CylinderX,
+    /// The bottom and top edges wrap around to each other, and particles bounce off the left and
+    /// right edges.
+    CylinderY,
+    /// Like [`Topology::Torus`], but crossing the left or right edge mirrors the `y` coordinate,
+    /// making a Klein bottle. The particles crossing it are mirrored along with their velocity.
+    Klein,
+}
+
+impl Topology {
+    fn wraps_x(self) -> bool {
+        self != Self::CylinderY
+    }
+
+    fn wraps_y(self) -> bool {
+        self != Self::CylinderX
+    }
+}
+
+/// The rectangle the particles live in. By default, the world wraps around from each edge to the
+/// opposite one, making it a torus, but other identifications of the edges can be chosen with
+/// `topology`.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct WorldBounds {
+    pub min: Vec2,
+    pub max: Vec2,
+    pub topology: Topology,
+}
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        Self {
+            min: Vec2::splat(-1.0),
+            max: Vec2::splat(1.0),
+            topology: Topology::Torus,
+        }
+    }
+}
+
+impl WorldBounds {
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmplt(self.max).all()
+    }
+
+    pub fn clamp(&self, point: Vec2) -> Vec2 {
+        point.clamp(self.min, self.max)
+    }
+
+    /// Brings `point` back into the world, into `min..max` along the axes that wrap around and
+    /// into `min..=max` along the others, see [`Self::wrap_motion`].
+    pub fn wrap(&self, point: RealVec2) -> RealVec2 {
+        self.wrap_motion(point, RealVec2::ZERO).0
+    }
+
+    /// Brings a particle that moved to `position` with `velocity` back into the world, following
+    /// the [`Topology`]: it wraps around the edges that wrap, mirrored if it crossed the twisted
+    /// edges of a Klein bottle an odd number of times, and bounces off the other edges.
+    pub fn wrap_motion(&self, position: RealVec2, velocity: RealVec2) -> (RealVec2, RealVec2) {
+        let (min, max, size) = (
+            real_vec2(self.min),
+            real_vec2(self.max),
+            real_vec2(self.size()),
+        );
+        let (mut position, mut velocity) = (position, velocity);
+
+        if self.topology.wraps_x() {
+            let turns = ((position.x - min.x) / size.x).floor();
+            position.x -= size.x * turns;
+            if self.topology == Topology::Klein && turns.rem_euclid(2.0) == 1.0 {
+                position.y = min.y + max.y - position.y;
+                velocity.y = -velocity.y;
+            }
+        } else {
+            (position.x, velocity.x) = bounce(position.x, velocity.x, min.x, max.x);
+        }
+
+        if self.topology.wraps_y() {
+            position.y -= size.y * ((position.y - min.y) / size.y).floor();
+        } else {
+            (position.y, velocity.y) = bounce(position.y, velocity.y, min.y, max.y);
+        }
+        (position, velocity)
+    }
+}
+
+/// Reflects a coordinate past `min` or `max` off that edge, along with its speed.
+fn bounce(position: Real, speed: Real, min: Real, max: Real) -> (Real, Real) {
+    let (position, speed) = if position < min {
+        (2.0 * min - position, speed.abs())
+    } else if position > max {
+        (2.0 * max - position, -speed.abs())
+    } else {
+        (position, speed)
+    };
+    // Particles moving farther than the size of the world in one step stop at the edge
+    (position.clamp(min, max), speed)
+}
+
+/// Returns the shortest vector from `a` to `b` in the world, going across the edges that wrap
+/// around when shorter, following the [`Topology`] of `bounds`. Along the axes that wrap around,
+/// each component lies within half the size of the world.
+///
+/// On a Klein bottle, the vector from `b` to `a` across the twisted edges has the same `y`
+/// component as the vector from `a` to `b`, rather than the opposite one, since each particle sees
+/// the other mirrored.
+///
+/// This is the one place wrapping is handled: every feature measuring distances between particles
+/// should go through it or [`toroidal_dist`].
+pub fn toroidal_delta(a: RealVec2, b: RealVec2, bounds: &WorldBounds) -> RealVec2 {
+    let size = real_vec2(bounds.size());
+    let shortest = |delta: Real, size: Real| delta - size * (delta / size).round();
+    match bounds.topology {
+        Topology::Torus => {
+            let delta = b - a;
+            delta - size * (delta / size).round()
+        }
+        Topology::CylinderX => RealVec2::new(shortest(b.x - a.x, size.x), b.y - a.y),
+        Topology::CylinderY => RealVec2::new(b.x - a.x, shortest(b.y - a.y, size.y)),
+        Topology::Klein => {
+            let (a, b) = (bounds.wrap(a), bounds.wrap(b));
+            // `b` and its nearest images across the twisted edges, which are mirrored
+            let mirrored_y = real(bounds.min.y + bounds.max.y) - b.y;
+            [(0.0, b.y), (-size.x, mirrored_y), (size.x, mirrored_y)]
+                .into_iter()
+                .map(|(offset, y)| RealVec2::new(b.x + offset - a.x, shortest(y - a.y, size.y)))
+                .min_by(|delta, other| delta.length_squared().total_cmp(&other.length_squared()))
+                .expect("`b` has images")
+        }
+    }
+}
+
+/// Returns the length of [`toroidal_delta`].
+pub fn toroidal_dist(a: RealVec2, b: RealVec2, bounds: &WorldBounds) -> Real {
+    toroidal_delta(a, b, bounds).length()
+}
+
+/// [`toroidal_delta`] between single-precision points, for rendering.
+pub(crate) fn toroidal_delta_f32(a: Vec2, b: Vec2, bounds: &WorldBounds) -> Vec2 {
+    single_vec2(toroidal_delta(real_vec2(a), real_vec2(b), bounds))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    fn random_point(rng: &mut StdRng, bounds: &WorldBounds) -> RealVec2 {
+        let x = rng.gen_range(bounds.min.x..bounds.max.x);
+        let y = rng.gen_range(bounds.min.y..bounds.max.y);
+        RealVec2::new(real(x), real(y))
+    }
+
+    #[test]
+    fn toroidal_delta_matches_brute_force() {
+        let bounds = WorldBounds {
+            min: Vec2::new(-1.0, -0.5),
+            max: Vec2::new(2.0, 1.5),
+            topology: Topology::Torus,
+        };
+        let size = real_vec2(bounds.size());
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..1000 {
+            let (a, b) = (
+                random_point(&mut rng, &bounds),
+                random_point(&mut rng, &bounds),
+            );
+            // The nearest of the images of `b` in the surrounding copies of the world
+            let expected = (-1..=1)
+                .flat_map(|x| (-1..=1).map(move |y| RealVec2::new(x as Real, y as Real)))
+                .map(|offset| b + offset * size - a)
+                .min_by(|delta, other| delta.length_squared().total_cmp(&other.length_squared()))
+                .unwrap();
+
+            let delta = toroidal_delta(a, b, &bounds);
+            assert!(
+                (delta - expected).length() < 1e-4,
+                "{a} to {b}: {delta} instead of {expected}"
+            );
+            assert!((toroidal_dist(a, b, &bounds) - expected.length()).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn topologies_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for topology in [
+            Topology::Torus,
+            Topology::CylinderX,
+            Topology::CylinderY,
+            Topology::Klein,
+        ] {
+            let bounds = WorldBounds {
+                min: Vec2::new(-1.0, -0.5),
+                max: Vec2::new(2.0, 1.5),
+                topology,
+            };
+            let size = real_vec2(bounds.size());
+            let offsets = |wraps| if wraps { -2..=2 } else { 0..=0 };
+
+            for _ in 0..1000 {
+                let (a, b) = (
+                    random_point(&mut rng, &bounds),
+                    random_point(&mut rng, &bounds),
+                );
+                // The images of `b` in the copies of the world glued along the edges that wrap,
+                // mirrored across the twisted edges of a Klein bottle
+                let images = offsets(topology.wraps_x()).flat_map(|x: i32| {
+                    let mirrored = topology == Topology::Klein && x % 2 != 0;
+                    let y = if mirrored {
+                        real(bounds.min.y + bounds.max.y) - b.y
+                    } else {
+                        b.y
+                    };
+                    offsets(topology.wraps_y()).map(move |offset_y: i32| {
+                        RealVec2::new(b.x + x as Real * size.x, y + offset_y as Real * size.y)
+                    })
+                });
+                let expected = images
+                    .map(|image| image - a)
+                    .min_by(|delta, other| {
+                        delta.length_squared().total_cmp(&other.length_squared())
+                    })
+                    .unwrap();
+
+                let delta = toroidal_delta(a, b, &bounds);
+                assert!(
+                    (delta - expected).length() < 1e-4,
+                    "{topology:?}, {a} to {b}: {delta} instead of {expected}"
+                );
+            }
+        }
+    }
+}
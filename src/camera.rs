@@ -0,0 +1,208 @@
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    ecs::system::SystemParam,
+    prelude::*,
+    render::{
+        camera::{ScalingMode, Viewport},
+        view::RenderLayers,
+    },
+};
+
+use crate::{
+    bounds::toroidal_delta_f32,
+    precision::{real_vec2, single_vec2},
+    Position, SimulationId, WorldBounds,
+};
+
+/// How quickly the camera catches up with its target: the remaining distance is divided by `e`
+/// every `1 / FOLLOW_SPEED` seconds.
+const FOLLOW_SPEED: f32 = 5.0;
+
+/// The particle followed by the camera, if any.
+///
+/// When set, the camera of the simulation of the particle smoothly moves toward the particle each
+/// frame, along the shortest path on the torus, and wraps around with it when it crosses an edge
+/// of the world. When cleared, or when the particle is despawned, that camera jumps back to the
+/// center of the world once, and is left alone afterwards. Only the camera position is
+/// controlled: its projection and the cameras of the other simulations are left untouched.
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub struct CameraTarget(pub Option<Entity>);
+
+/// The position of the cursor in the world, as seen by the camera of the main simulation.
+#[derive(SystemParam)]
+pub(crate) struct CursorPosition<'w, 's> {
+    windows: Res<'w, Windows>,
+    cameras: Query<
+        'w,
+        's,
+        (
+            &'static Camera,
+            &'static GlobalTransform,
+            &'static ParticleCamera,
+        ),
+    >,
+}
+
+impl CursorPosition<'_, '_> {
+    /// Returns `None` when the cursor is outside of the window or of the viewport of the main
+    /// simulation.
+    pub(crate) fn world_position(&self) -> Option<Vec2> {
+        let cursor = self.windows.get_primary()?.cursor_position()?;
+        let (camera, transform, _) = self
+            .cameras
+            .iter()
+            .find(|(.., camera)| camera.simulation == SimulationId::MAIN)?;
+
+        // The main viewport is the leftmost one, so its origin is the origin of the window
+        let viewport_size = camera.logical_viewport_size()?;
+        if cursor.x > viewport_size.x {
+            return None;
+        }
+        let ndc = 2.0 * cursor / viewport_size - Vec2::ONE;
+        let ndc_to_world = transform.compute_matrix() * camera.projection_matrix().inverse();
+        Some(ndc_to_world.project_point3(ndc.extend(0.0)).truncate())
+    }
+}
+
+/// Marks the cameras spawned by the plugin, one per simulation.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub(crate) struct ParticleCamera {
+    simulation: SimulationId,
+}
+
+pub(crate) fn build(app: &mut App, simulation_count: usize) {
+    app.init_resource::<CameraTarget>()
+        .add_startup_system(move |commands: Commands, bounds: Res<WorldBounds>| {
+            setup_cameras(commands, bounds, simulation_count)
+        })
+        .add_system(follow_target);
+    if simulation_count > 1 {
+        app.add_system(move |windows: Res<Windows>, cameras: Query<_, _>| {
+            update_viewports(windows, cameras, simulation_count)
+        });
+    }
+}
+
+fn setup_cameras(mut commands: Commands, bounds: Res<WorldBounds>, simulation_count: usize) {
+    let (center, half_size) = (bounds.center(), bounds.size() / 2.0);
+    for id in 0..simulation_count {
+        let mut camera_bundle = Camera2dBundle::default();
+        camera_bundle.transform.translation.x = center.x;
+        camera_bundle.transform.translation.y = center.y;
+
+        let mut camera = commands.spawn((
+            Camera2dBundle {
+                camera: Camera {
+                    priority: id as isize,
+                    ..Default::default()
+                },
+                camera_2d: Camera2d {
+                    // Only the first camera clears the window, which would otherwise clear the
+                    // viewports rendered before
+                    clear_color: if id == 0 {
+                        ClearColorConfig::Default
+                    } else {
+                        ClearColorConfig::None
+                    },
+                },
+                projection: OrthographicProjection {
+                    left: -half_size.x,
+                    right: half_size.x,
+                    bottom: -half_size.y,
+                    top: half_size.y,
+                    scaling_mode: ScalingMode::None,
+                    ..Default::default()
+                },
+                ..camera_bundle
+            },
+            ParticleCamera {
+                simulation: SimulationId(id),
+            },
+        ));
+        if simulation_count > 1 {
+            camera.insert(RenderLayers::layer(id as u8));
+        }
+        // The UI is only drawn once, by the main camera
+        if id > 0 {
+            camera.insert(UiCameraConfig { show_ui: false });
+        }
+    }
+}
+
+/// Splits the window into one vertical strip per simulation.
+fn update_viewports(
+    windows: Res<Windows>,
+    mut cameras: Query<(&mut Camera, &ParticleCamera)>,
+    simulation_count: usize,
+) {
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+    let width = window.physical_width() / simulation_count as u32;
+    let height = window.physical_height();
+
+    for (mut camera, particle_camera) in &mut cameras {
+        let position = UVec2::new(particle_camera.simulation.0 as u32 * width, 0);
+        let size = UVec2::new(width, height);
+        let current = camera
+            .viewport
+            .as_ref()
+            .map(|viewport| (viewport.physical_position, viewport.physical_size));
+        // Avoid triggering change detection every frame
+        if current != Some((position, size)) {
+            camera.viewport = Some(Viewport {
+                physical_position: position,
+                physical_size: size,
+                ..Default::default()
+            });
+        }
+    }
+}
+
+fn follow_target(
+    time: Res<Time>,
+    bounds: Res<WorldBounds>,
+    mut target: ResMut<CameraTarget>,
+    // The simulation of the camera following the target on the previous frame
+    mut following: Local<Option<SimulationId>>,
+    particles: Query<(&Position, &SimulationId)>,
+    mut cameras: Query<(&mut Transform, &ParticleCamera)>,
+) {
+    let target = target.0.and_then(|entity| match particles.get(entity) {
+        Ok((position, &simulation)) => Some((single_vec2(position.0), simulation)),
+        // The followed particle was despawned
+        Err(_) => {
+            target.0 = None;
+            None
+        }
+    });
+
+    let Some((target_position, simulation)) = target else {
+        // Recenter the camera that stopped following, once
+        if let Some(simulation) = following.take() {
+            for (mut transform, camera) in &mut cameras {
+                if camera.simulation == simulation {
+                    let center = bounds.center();
+                    transform.translation.x = center.x;
+                    transform.translation.y = center.y;
+                }
+            }
+        }
+        return;
+    };
+    *following = Some(simulation);
+
+    let t = 1.0 - f32::exp(-FOLLOW_SPEED * time.delta_seconds());
+    for (mut transform, camera) in &mut cameras {
+        if camera.simulation != simulation {
+            continue;
+        }
+        let mut camera_position = transform.translation.truncate();
+
+        camera_position += t * toroidal_delta_f32(camera_position, target_position, &bounds);
+        let camera_position = single_vec2(bounds.wrap(real_vec2(camera_position)));
+
+        transform.translation.x = camera_position.x;
+        transform.translation.y = camera_position.y;
+    }
+}
@@ -0,0 +1,93 @@
+//! Driving an app with a deterministic clock instead of the wall clock, for tests and offline
+//! runs.
+
+use bevy::prelude::*;
+
+use std::time::Duration;
+
+/// Advances the [`Time`] of `app` by exactly `dt` seconds, then runs one update.
+///
+/// `app` must not update its [`Time`] on its own, so it should be built without the
+/// [`TimePlugin`](bevy::time::TimePlugin) and with a [`Time`] resource inserted instead. The time
+/// step of the simulation is still capped by [`MaxDelta`](crate::MaxDelta), and replaced by the
+/// [`AdaptiveTimestep`](crate::AdaptiveTimestep) when there is one.
+pub fn advance(app: &mut App, dt: f32) {
+    let mut time = app.world.resource_mut::<Time>();
+    let last_update = match time.last_update() {
+        Some(last_update) => last_update,
+        None => {
+            // The first update of the clock has no previous instant to measure a delta from
+            let startup = time.startup();
+            time.update_with_instant(startup);
+            startup
+        }
+    };
+    time.update_with_instant(last_update + Duration::from_secs_f32(dt));
+    app.update();
+}
+
+/// Runs `steps` updates of `app`, each advancing its clock by `dt` seconds, see [`advance`].
+pub fn advance_steps(app: &mut App, dt: f32, steps: usize) {
+    for _ in 0..steps {
+        advance(app, dt);
+    }
+}
+
+/// A headless app running `plugin`, driven by [`advance`].
+#[cfg(test)]
+pub(crate) fn headless_app(plugin: crate::ParticleLifePlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins.build().disable::<bevy::time::TimePlugin>())
+        .init_resource::<Time>()
+        .add_plugin(crate::ParticleLifePlugin {
+            headless: true,
+            ..plugin
+        });
+    app
+}
+
+/// A seeded plugin with two colors that don't attract each other, for tests to adjust.
+#[cfg(test)]
+pub(crate) fn test_plugin(initial_particles: Vec<crate::Particle>) -> crate::ParticleLifePlugin {
+    use crate::{Attraction, AttractionRadius, ColorAttractions};
+
+    crate::ParticleLifePlugin {
+        initial_particles,
+        colors: vec![Color::RED, Color::GREEN],
+        color_attractions: ColorAttractions(vec![vec![Attraction(0.0); 2]; 2]),
+        attraction_radius: AttractionRadius {
+            rmin: 0.05,
+            rmax: 0.3,
+        },
+        seed: Some(0),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        precision::{real, single_vec2},
+        ColorId, Particle, Position, RealVec2, SimulationTime, Velocity,
+    };
+
+    #[test]
+    fn advance_moves_the_simulation_by_exact_steps() {
+        let particle = Particle {
+            position: Position(RealVec2::ZERO),
+            velocity: Velocity(RealVec2::new(real(0.5), real(0.0))),
+            color: ColorId(0),
+        };
+        let mut app = headless_app(test_plugin(vec![particle]));
+
+        advance_steps(&mut app, 0.01, 4);
+
+        let elapsed = app.world.resource::<SimulationTime>().elapsed;
+        assert!((elapsed - 0.04).abs() < 1e-6, "elapsed {elapsed}");
+        let position = app.world.query::<&Position>().single(&app.world).0;
+        let position = single_vec2(position);
+        assert!((position.x - 0.02).abs() < 1e-5, "position {position}");
+        assert_eq!(position.y, 0.0);
+    }
+}
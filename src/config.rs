@@ -0,0 +1,233 @@
+//! Loading the settings of a [`ParticleLifePlugin`] from text files, so that apps can ship a
+//! default configuration and let users override parts of it.
+//!
+//! # Format
+//!
+//! Each line sets one setting: its name, then its values, separated by spaces. Settings missing
+//! from the file are left unchanged, and empty lines and lines starting with `#` are ignored.
+//!
+//! ```text
+//! seed 42
+//! bounds -1 -1 1 1
+//! topology klein
+//! colors #ff0000 #00ff00
+//! color_attractions 0.3,-0.1;0.2,0.3
+//! attraction 0 1 0.5
+//! attraction_radius 0.04 0.4
+//! peak_fraction 0.5
+//! kernel smoothstep
+//! smooth_cutoff 0.2
+//! friction 0.1
+//! max_delta 0.033333335
+//! ```
+//!
+//! `color_attractions` replaces the whole matrix, its rows being separated by `;` and their
+//! entries by `,`, while `attraction i j a` only sets the attraction of the `i`th color by the
+//! `j`th color to `a`, after any `color_attractions`.
+
+use bevy::prelude::*;
+
+use std::{error::Error, fmt, io, path::Path, str::FromStr};
+
+use crate::{
+    replay::Tokens, Attraction, AttractionRadius, ColorAttractions, Friction, Kernel, MaxDelta,
+    ParticleLifeError, ParticleLifePlugin, PeakFraction, SmoothCutoff, Topology, WorldBounds,
+};
+
+/// Settings read from a configuration file, see the [module documentation](self) for its format.
+/// Settings that are `None` or empty weren't given.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PartialConfig {
+    pub seed: Option<u64>,
+    /// The extent of the world, its topology being given by `topology`.
+    pub bounds: Option<WorldBounds>,
+    pub topology: Option<Topology>,
+    pub colors: Option<Vec<Color>>,
+    pub color_attractions: Option<ColorAttractions>,
+    /// Single entries of the attraction matrix, as their row, column and attraction.
+    pub attractions: Vec<(usize, usize, Attraction)>,
+    pub attraction_radius: Option<AttractionRadius>,
+    pub peak_fraction: Option<PeakFraction>,
+    pub kernel: Option<Kernel>,
+    pub smooth_cutoff: Option<SmoothCutoff>,
+    pub friction: Option<Friction>,
+    pub max_delta: Option<MaxDelta>,
+}
+
+#[derive(Debug)]
+pub enum ConfigFileError {
+    Io(io::Error),
+    /// The file doesn't set a required setting.
+    MissingSetting(&'static str),
+    /// Line `line`, counting from `1`, is malformed.
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ConfigFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::MissingSetting(setting) => write!(f, "`{setting}` is not set"),
+            Self::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl Error for ConfigFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::MissingSetting(_) | Self::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ConfigFileError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl PartialConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ParticleLifeError> {
+        Ok(std::fs::read_to_string(path)?.parse::<Self>()?)
+    }
+
+    /// Overwrites the settings of `plugin` given by this configuration.
+    ///
+    /// Single attractions out of the matrix grow it with zero attractions, which
+    /// [`ParticleLifePlugin::validate`] reports unless the colors are changed to match.
+    pub fn apply(self, plugin: &mut ParticleLifePlugin) {
+        if let Some(seed) = self.seed {
+            plugin.seed = Some(seed);
+        }
+        if let Some(bounds) = self.bounds {
+            plugin.bounds.min = bounds.min;
+            plugin.bounds.max = bounds.max;
+        }
+        if let Some(topology) = self.topology {
+            plugin.bounds.topology = topology;
+        }
+        if let Some(colors) = self.colors {
+            plugin.colors = colors;
+        }
+        if let Some(color_attractions) = self.color_attractions {
+            plugin.color_attractions = color_attractions;
+        }
+        set_attractions(&mut plugin.color_attractions, self.attractions);
+        if let Some(attraction_radius) = self.attraction_radius {
+            plugin.attraction_radius = attraction_radius;
+        }
+        if let Some(peak_fraction) = self.peak_fraction {
+            plugin.peak_fraction = peak_fraction;
+        }
+        if let Some(kernel) = self.kernel {
+            plugin.kernel = kernel;
+        }
+        if let Some(smooth_cutoff) = self.smooth_cutoff {
+            plugin.smooth_cutoff = smooth_cutoff;
+        }
+        if let Some(friction) = self.friction {
+            plugin.friction = friction;
+        }
+        if let Some(max_delta) = self.max_delta {
+            plugin.max_delta = max_delta;
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let mut tokens = Tokens(line.split_whitespace());
+        match tokens.next()? {
+            "seed" => self.seed = Some(tokens.parse()?),
+            "bounds" => {
+                self.bounds = Some(WorldBounds {
+                    min: Vec2::new(tokens.parse()?, tokens.parse()?),
+                    max: Vec2::new(tokens.parse()?, tokens.parse()?),
+                    topology: Topology::Torus,
+                })
+            }
+            "topology" => self.topology = Some(tokens.topology()?),
+            "colors" => self.colors = Some(tokens.colors()?),
+            "color_attractions" => self.color_attractions = Some(tokens.color_attractions()?),
+            "attraction" => self.attractions.push((
+                tokens.parse()?,
+                tokens.parse()?,
+                Attraction(tokens.parse()?),
+            )),
+            "attraction_radius" => {
+                self.attraction_radius = Some(AttractionRadius {
+                    rmin: tokens.parse()?,
+                    rmax: tokens.parse()?,
+                })
+            }
+            "peak_fraction" => self.peak_fraction = Some(PeakFraction(tokens.parse()?)),
+            "kernel" => self.kernel = Some(tokens.kernel()?),
+            "smooth_cutoff" => {
+                self.smooth_cutoff = Some(SmoothCutoff {
+                    window: tokens.parse()?,
+                })
+            }
+            "friction" => self.friction = Some(Friction(tokens.parse()?)),
+            "max_delta" => self.max_delta = Some(MaxDelta(tokens.parse()?)),
+            setting => return Err(format!("unknown setting `{setting}`")),
+        }
+        tokens.finish()
+    }
+}
+
+/// Sets single entries of `color_attractions`, growing it with zero attractions as needed.
+pub(crate) fn set_attractions(
+    color_attractions: &mut ColorAttractions,
+    attractions: Vec<(usize, usize, Attraction)>,
+) {
+    let rows = &mut color_attractions.0;
+    for (row, column, attraction) in attractions {
+        if rows.len() <= row {
+            rows.resize(row + 1, Vec::new());
+        }
+        if rows[row].len() <= column {
+            rows[row].resize(column + 1, Attraction(0.0));
+        }
+        rows[row][column] = attraction;
+    }
+}
+
+impl FromStr for PartialConfig {
+    type Err = ConfigFileError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config = Self::default();
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            config
+                .parse_line(line)
+                .map_err(|message| ConfigFileError::Parse {
+                    line: index + 1,
+                    message,
+                })?;
+        }
+        Ok(config)
+    }
+}
+
+/// Loads the configuration at `base`, then the one at `overrides` on top of it, the settings
+/// missing from both being the defaults of [`ParticleLifePlugin`], and validates the result.
+///
+/// This reads the files synchronously, so apps loading it while running should do so in a task
+/// of the [`IoTaskPool`](bevy::tasks::IoTaskPool).
+pub fn load_config_with_overrides(
+    base: impl AsRef<Path>,
+    overrides: impl AsRef<Path>,
+) -> Result<ParticleLifePlugin, ParticleLifeError> {
+    let mut plugin = ParticleLifePlugin::default();
+    PartialConfig::load(base)?.apply(&mut plugin);
+    PartialConfig::load(overrides)?.apply(&mut plugin);
+    plugin.validate()?;
+    Ok(plugin)
+}
@@ -0,0 +1,258 @@
+use image::ImageError;
+
+use std::{error::Error, fmt, io};
+
+use crate::{
+    AttractionImageError, ConfigError, ConfigFileError, ConfigProblem, ReplayLogError,
+    SnapshotError,
+};
+
+/// Any error of the crate, for apps that handle them all the same way.
+///
+/// The errors of each feature, such as [`SnapshotError`] or [`ConfigFileError`], convert into it
+/// with `?`. They are kept as is, available through [`Error::source`], except for their I/O
+/// errors which all become [`Self::IoError`].
+#[derive(Debug)]
+pub enum ParticleLifeError {
+    /// The colors, the attraction matrices or the attraction images don't have matching
+    /// dimensions.
+    DimensionMismatch(DimensionMismatch),
+    /// Particles or reactions have colors out of the palette.
+    InvalidColorIndex(ConfigError),
+    /// An attraction radius doesn't have `rmin < rmax`.
+    InvalidRadius(ConfigError),
+    /// Several kinds of configuration problems at once, or problems of none of the kinds above,
+    /// see [`ConfigError::0`].
+    InvalidConfig(ConfigError),
+    IoError(io::Error),
+    /// A file is malformed.
+    ParseError(ParseError),
+}
+
+/// The source of a [`ParticleLifeError::DimensionMismatch`].
+#[derive(Debug)]
+pub enum DimensionMismatch {
+    /// Only has [`ConfigProblem::NoColors`], [`ConfigProblem::AttractionRows`] and
+    /// [`ConfigProblem::AttractionColumns`] problems.
+    Config(ConfigError),
+    /// Is an [`AttractionImageError::DimensionMismatch`].
+    AttractionImage(AttractionImageError),
+}
+
+/// The source of a [`ParticleLifeError::ParseError`], never an I/O error.
+#[derive(Debug)]
+pub enum ParseError {
+    ConfigFile(ConfigFileError),
+    ReplayLog(ReplayLogError),
+    Snapshot(SnapshotError),
+    AttractionImage(AttractionImageError),
+}
+
+impl fmt::Display for ParticleLifeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DimensionMismatch(error) => write!(f, "{error}"),
+            Self::InvalidColorIndex(error)
+            | Self::InvalidRadius(error)
+            | Self::InvalidConfig(error) => write!(f, "{error}"),
+            Self::IoError(error) => write!(f, "{error}"),
+            Self::ParseError(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for ParticleLifeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::DimensionMismatch(error) => error.as_error(),
+            Self::InvalidColorIndex(error)
+            | Self::InvalidRadius(error)
+            | Self::InvalidConfig(error) => Some(error),
+            Self::IoError(error) => Some(error),
+            Self::ParseError(error) => error.as_error(),
+        }
+    }
+}
+
+impl fmt::Display for DimensionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config(error) => write!(f, "{error}"),
+            Self::AttractionImage(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl DimensionMismatch {
+    /// The error of the feature that found the mismatch.
+    fn as_error(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Config(error) => Some(error),
+            Self::AttractionImage(error) => Some(error),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConfigFile(error) => write!(f, "{error}"),
+            Self::ReplayLog(error) => write!(f, "{error}"),
+            Self::Snapshot(error) => write!(f, "{error}"),
+            Self::AttractionImage(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl ParseError {
+    /// The error of the feature that failed to parse the file.
+    fn as_error(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::ConfigFile(error) => Some(error),
+            Self::ReplayLog(error) => Some(error),
+            Self::Snapshot(error) => Some(error),
+            Self::AttractionImage(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for ParticleLifeError {
+    fn from(error: io::Error) -> Self {
+        Self::IoError(error)
+    }
+}
+
+/// Classifies the error by the kind of all of its problems, [`Self::InvalidConfig`] when they are
+/// of different kinds.
+impl From<ConfigError> for ParticleLifeError {
+    fn from(error: ConfigError) -> Self {
+        #[derive(PartialEq)]
+        enum Kind {
+            Dimensions,
+            ColorIndex,
+            Radius,
+            Other,
+        }
+        let kind = |problem: &ConfigProblem| match problem {
+            ConfigProblem::NoColors
+            | ConfigProblem::AttractionRows { .. }
+            | ConfigProblem::AttractionColumns { .. } => Kind::Dimensions,
+            ConfigProblem::ColorOutOfRange { .. }
+            | ConfigProblem::ReactionColorOutOfRange { .. } => Kind::ColorIndex,
+            ConfigProblem::InvalidAttractionRadius { .. } => Kind::Radius,
+            ConfigProblem::InvalidAdaptiveTimestep(_) => Kind::Other,
+        };
+
+        let mut kinds = error.0.iter().map(kind);
+        let first = kinds.next();
+        if !kinds.all(|kind| Some(kind) == first) {
+            return Self::InvalidConfig(error);
+        }
+        match first {
+            Some(Kind::Dimensions) => Self::DimensionMismatch(DimensionMismatch::Config(error)),
+            Some(Kind::ColorIndex) => Self::InvalidColorIndex(error),
+            Some(Kind::Radius) => Self::InvalidRadius(error),
+            Some(Kind::Other) | None => Self::InvalidConfig(error),
+        }
+    }
+}
+
+impl From<ConfigFileError> for ParticleLifeError {
+    fn from(error: ConfigFileError) -> Self {
+        match error {
+            ConfigFileError::Io(error) => Self::IoError(error),
+            ConfigFileError::MissingSetting(_) | ConfigFileError::Parse { .. } => {
+                Self::ParseError(ParseError::ConfigFile(error))
+            }
+        }
+    }
+}
+
+impl From<AttractionImageError> for ParticleLifeError {
+    fn from(error: AttractionImageError) -> Self {
+        match error {
+            AttractionImageError::Image(ImageError::IoError(error)) => Self::IoError(error),
+            AttractionImageError::Image(_) => Self::ParseError(ParseError::AttractionImage(error)),
+            AttractionImageError::DimensionMismatch { .. } => {
+                Self::DimensionMismatch(DimensionMismatch::AttractionImage(error))
+            }
+        }
+    }
+}
+
+impl From<ImageError> for ParticleLifeError {
+    fn from(error: ImageError) -> Self {
+        AttractionImageError::Image(error).into()
+    }
+}
+
+impl From<ReplayLogError> for ParticleLifeError {
+    fn from(error: ReplayLogError) -> Self {
+        match error {
+            ReplayLogError::Io(error) => Self::IoError(error),
+            ReplayLogError::Parse { .. } => Self::ParseError(ParseError::ReplayLog(error)),
+        }
+    }
+}
+
+impl From<SnapshotError> for ParticleLifeError {
+    fn from(error: SnapshotError) -> Self {
+        match error {
+            SnapshotError::Io(error) => Self::IoError(error),
+            _ => Self::ParseError(ParseError::Snapshot(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AttractionRadius, ColorId, SimulationId};
+
+    fn radius_problem() -> ConfigProblem {
+        ConfigProblem::InvalidAttractionRadius {
+            simulation: SimulationId::MAIN,
+            radius: AttractionRadius {
+                rmin: 1.0,
+                rmax: 0.0,
+            },
+        }
+    }
+
+    #[test]
+    fn config_errors_are_classified_by_all_problems() {
+        let color_problem = ConfigProblem::ColorOutOfRange {
+            simulation: SimulationId::MAIN,
+            colors: 2,
+            index: 0,
+            color: ColorId(2),
+            count: 1,
+        };
+
+        let error = ConfigError(vec![radius_problem(), radius_problem()]);
+        assert!(matches!(error.into(), ParticleLifeError::InvalidRadius(_)));
+        let error = ConfigError(vec![ConfigProblem::NoColors]);
+        assert!(matches!(
+            error.into(),
+            ParticleLifeError::DimensionMismatch(DimensionMismatch::Config(_))
+        ));
+        let error = ConfigError(vec![color_problem.clone()]);
+        assert!(matches!(
+            error.into(),
+            ParticleLifeError::InvalidColorIndex(_)
+        ));
+        let error = ConfigError(vec![color_problem, radius_problem()]);
+        assert!(matches!(error.into(), ParticleLifeError::InvalidConfig(_)));
+    }
+
+    #[test]
+    fn sources_are_kept() {
+        let error = ParticleLifeError::from(ConfigError(vec![radius_problem()]));
+        let source = error.source().unwrap().downcast_ref::<ConfigError>();
+        assert_eq!(source, Some(&ConfigError(vec![radius_problem()])));
+
+        let error = ParticleLifeError::from(SnapshotError::Corrupted);
+        let source = error.source().unwrap().downcast_ref::<SnapshotError>();
+        assert!(matches!(source, Some(SnapshotError::Corrupted)));
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+
+use crate::{lines::Lines, precision::single_vec2, Position, SimulationId, Velocity, WorldBounds};
+
+/// Draws the average velocity of the particles of the main simulation over a grid of
+/// `resolution` by `resolution` cells, as an arrow at the center of each cell, to show the flow at
+/// a glance.
+///
+/// The arrows are scaled so that the one of the fastest cell spans most of its cell, and go from
+/// blue for slow cells to red for the fastest one. Empty cells have no arrow.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct FlowField {
+    pub resolution: usize,
+    pub enabled: bool,
+}
+
+impl Default for FlowField {
+    fn default() -> Self {
+        Self {
+            resolution: 16,
+            enabled: false,
+        }
+    }
+}
+
+/// The length of the heads of the arrows relative to their length.
+const HEAD_LENGTH: f32 = 0.3;
+
+pub(crate) fn build(app: &mut App, flow_field: FlowField) {
+    app.insert_resource(flow_field).add_system(draw_flow_field);
+}
+
+fn draw_flow_field(
+    flow_field: Res<FlowField>,
+    bounds: Res<WorldBounds>,
+    mut lines: ResMut<Lines>,
+    query: Query<(&Position, &Velocity, &SimulationId)>,
+) {
+    let resolution = flow_field.resolution;
+    if !flow_field.enabled || resolution == 0 {
+        return;
+    }
+    let cell_size = bounds.size() / resolution as f32;
+
+    // The sum of the velocities and the number of particles of each cell
+    let mut cells = vec![(Vec2::ZERO, 0_u32); resolution * resolution];
+    for (position, velocity, &simulation) in &query {
+        if simulation != SimulationId::MAIN {
+            continue;
+        }
+        let cell = ((single_vec2(position.0) - bounds.min) / cell_size)
+            .floor()
+            .clamp(Vec2::ZERO, Vec2::splat((resolution - 1) as f32));
+        let (sum, count) = &mut cells[cell.y as usize * resolution + cell.x as usize];
+        *sum += single_vec2(velocity.0);
+        *count += 1;
+    }
+
+    let averages: Vec<_> = cells
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, (_, count))| count > 0)
+        .map(|(cell, (sum, count))| (cell, sum / count as f32))
+        .collect();
+    let max_speed = averages
+        .iter()
+        .map(|(_, velocity)| velocity.length())
+        .fold(0.0, f32::max);
+    if max_speed == 0.0 {
+        return;
+    }
+
+    let scale = 0.9 * cell_size.min_element() / max_speed;
+    for (cell, velocity) in averages {
+        let center = bounds.min
+            + cell_size * Vec2::new((cell % resolution) as f32, (cell / resolution) as f32)
+            + cell_size / 2.0;
+        let arrow = velocity * scale;
+        let (start, end) = (center - arrow / 2.0, center + arrow / 2.0);
+
+        let speed = velocity.length() / max_speed;
+        let color = Color::rgb(speed, 0.0, 1.0 - speed);
+        lines.line(start, end, color);
+        let head = -arrow * HEAD_LENGTH;
+        for angle in [-0.5, 0.5_f32] {
+            lines.line(end, end + Vec2::from_angle(angle).rotate(head), color);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+
+use crate::{
+    background, precision::real, recenter, update_velocity, Real, SimulationTime, Velocity,
+};
+
+/// How quickly particles slow down, in `1 / s`: without forces, velocities are divided by `e`
+/// every `1 / Friction` seconds. `0` disables friction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Resource)]
+pub struct Friction(pub f32);
+
+/// A calmer start for simulations starting at rest, which would otherwise expand abruptly as soon
+/// as forces apply.
+///
+/// During the first `duration` seconds of simulated time, the friction ramps linearly from
+/// `initial_friction` to the [`Friction`] of the simulation, damping the initial burst.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct SettlePhase {
+    pub duration: f32,
+    pub initial_friction: f32,
+}
+
+impl SettlePhase {
+    /// The friction applied at the simulated time `elapsed`, when the simulation settles toward
+    /// `friction`.
+    pub fn friction_at(&self, elapsed: f32, friction: Friction) -> Friction {
+        if self.duration <= 0.0 || elapsed >= self.duration {
+            return friction;
+        }
+        let t = (elapsed / self.duration).max(0.0);
+        Friction(self.initial_friction + t * (friction.0 - self.initial_friction))
+    }
+}
+
+pub(crate) fn build(app: &mut App, friction: Friction, settle_phase: Option<SettlePhase>) {
+    app.insert_resource(friction).add_system(
+        apply_friction
+            .after(update_velocity)
+            .after(background::update_velocity_in_background)
+            .after(recenter),
+    );
+    if let Some(settle_phase) = settle_phase {
+        app.insert_resource(settle_phase);
+    }
+}
+
+fn apply_friction(
+    simulation_time: Res<SimulationTime>,
+    friction: Res<Friction>,
+    settle_phase: Option<Res<SettlePhase>>,
+    mut query: Query<&mut Velocity>,
+) {
+    let friction = match settle_phase {
+        Some(settle_phase) => settle_phase.friction_at(simulation_time.elapsed, *friction),
+        None => *friction,
+    };
+    if friction.0 == 0.0 {
+        return;
+    }
+
+    let factor = Real::exp(-real(friction.0 * simulation_time.delta));
+    for mut velocity in &mut query {
+        velocity.0 *= factor;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clock::{advance, headless_app, test_plugin},
+        precision::single_vec2,
+        ColorId, Particle, Position, RealVec2,
+    };
+
+    const SETTLE_PHASE: SettlePhase = SettlePhase {
+        duration: 1.0,
+        initial_friction: 10.0,
+    };
+
+    #[test]
+    fn settle_phase_interpolates_the_friction() {
+        let friction = Friction(2.0);
+        assert_eq!(SETTLE_PHASE.friction_at(0.0, friction), Friction(10.0));
+        assert_eq!(SETTLE_PHASE.friction_at(0.25, friction), Friction(8.0));
+        assert_eq!(SETTLE_PHASE.friction_at(0.5, friction), Friction(6.0));
+        assert_eq!(SETTLE_PHASE.friction_at(1.0, friction), friction);
+        assert_eq!(SETTLE_PHASE.friction_at(3.0, friction), friction);
+    }
+
+    #[test]
+    fn settling_particles_slow_down_with_the_interpolated_friction() {
+        let friction = Friction(2.0);
+        let particle = Particle {
+            position: Position(RealVec2::ZERO),
+            velocity: Velocity(RealVec2::X),
+            color: ColorId(0),
+        };
+        let mut app = headless_app(crate::ParticleLifePlugin {
+            friction,
+            settle_phase: Some(SETTLE_PHASE),
+            ..test_plugin(vec![particle])
+        });
+
+        let dt = 0.02;
+        let speed = |app: &mut App| {
+            let velocity = *app.world.query::<&Velocity>().single(&app.world);
+            single_vec2(velocity.0).length()
+        };
+        // Runs past the end of the settling window
+        for _ in 0..60 {
+            let before = speed(&mut app);
+            advance(&mut app, dt);
+            let after = speed(&mut app);
+
+            // The simulated time has already advanced when the friction applies
+            let elapsed = app.world.resource::<SimulationTime>().elapsed;
+            let effective = -(after / before).ln() / dt;
+            let expected = SETTLE_PHASE.friction_at(elapsed, friction).0;
+            assert!(
+                (effective - expected).abs() < 1e-3,
+                "at {elapsed}s the friction is {effective} instead of {expected}"
+            );
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{precision::real_vec2, Position, Real, RealVec2, Topology, WorldBounds};
+
+/// Offsets of the neighboring cells visited from each cell, besides the cell itself. Only half of
+/// the 8 surrounding cells are visited, the other half visiting this cell in turn, so that every
+/// pair of cells is considered exactly once.
+const HALF_NEIGHBORHOOD: [(isize, isize); 4] = [(1, 0), (1, 1), (0, 1), (-1, 1)];
+
+/// Keeps the grid size reasonable for tiny interaction radii.
+const MAX_CELLS_PER_SIDE: usize = 256;
+
+/// Particles bucketed into a toroidal grid of cells covering the world, each at least as wide and
+/// as high as the interaction radius. Any two particles closer than this radius are then either in
+/// the same cell or in adjacent cells.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct NeighborGrid {
+    columns: usize,
+    rows: usize,
+    origin: RealVec2,
+    cell_size: RealVec2,
+    /// Indices of the particles, sorted by cell.
+    particles: Vec<usize>,
+    /// The particles of cell `c` are `particles[cell_starts[c]..cell_starts[c + 1]]`.
+    cell_starts: Vec<usize>,
+    /// Whether crossing the left or right edge mirrors the rows, on a Klein bottle.
+    twisted: bool,
+}
+
+impl NeighborGrid {
+    /// Rebuilds the grid from the particle positions, with cells at least `min_cell_size` wide.
+    pub(crate) fn rebuild(
+        &mut self,
+        positions: &[Position],
+        min_cell_size: f32,
+        bounds: &WorldBounds,
+    ) {
+        let size = bounds.size();
+        self.columns = cells_along(size.x, min_cell_size);
+        self.rows = cells_along(size.y, min_cell_size);
+        self.twisted = bounds.topology == Topology::Klein;
+        if self.twisted && self.columns == 1 {
+            // The mirrored neighbors of a cell across the twisted edges could be any row
+            self.rows = 1;
+        }
+        self.origin = real_vec2(bounds.min);
+        self.cell_size = real_vec2(size) / RealVec2::new(self.columns as _, self.rows as _);
+
+        // Counting sort of the particles by cell
+        let cell_count = self.columns * self.rows;
+        self.cell_starts.clear();
+        self.cell_starts.resize(cell_count + 1, 0);
+        for position in positions {
+            let cell = self.cell_of(position);
+            self.cell_starts[cell + 1] += 1;
+        }
+        for cell in 0..cell_count {
+            self.cell_starts[cell + 1] += self.cell_starts[cell];
+        }
+
+        let mut next_slots = self.cell_starts.clone();
+        self.particles.clear();
+        self.particles.resize(positions.len(), 0);
+        for (index, position) in positions.iter().enumerate() {
+            let slot = &mut next_slots[self.cell_of(position)];
+            self.particles[*slot] = index;
+            *slot += 1;
+        }
+    }
+
+    /// Calls `f` once for each unordered pair of particles in the same or adjacent cells.
+    pub(crate) fn for_each_pair(&self, mut f: impl FnMut(usize, usize)) {
+        // Along an axis with a single cell, the neighbors along that axis are the cell itself
+        let neighborhood: Vec<_> = HALF_NEIGHBORHOOD
+            .into_iter()
+            .filter(|&(dx, dy)| (self.columns > 1 || dx == 0) && (self.rows > 1 || dy == 0))
+            .collect();
+
+        for y in 0..self.rows {
+            for x in 0..self.columns {
+                let cell = self.cell_particles(x, y);
+                for (i, &a) in cell.iter().enumerate() {
+                    for &b in &cell[i + 1..] {
+                        f(a, b);
+                    }
+                }
+
+                for &(dx, dy) in &neighborhood {
+                    // Mirroring breaks the symmetry the half neighborhood relies on, so the cells
+                    // across the twisted edges are all visited from the last column instead
+                    if self.twisted && self.crosses(x, dx) {
+                        continue;
+                    }
+                    let (neighbor_x, neighbor_y) = self.neighbor(x, y, dx, dy);
+                    let neighbor = self.cell_particles(neighbor_x, neighbor_y);
+                    for &a in cell {
+                        for &b in neighbor {
+                            f(a, b);
+                        }
+                    }
+                }
+
+                if self.twisted && self.columns > 1 && x == self.columns - 1 {
+                    let rows = if self.rows > 1 { -1..=1 } else { 0..=0 };
+                    for dy in rows {
+                        let (neighbor_x, neighbor_y) = self.neighbor(x, y, 1, dy);
+                        let neighbor = self.cell_particles(neighbor_x, neighbor_y);
+                        for &a in cell {
+                            for &b in neighbor {
+                                f(a, b);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Calls `f` once for each particle in the cells within `radius` of the cell of `point`,
+    /// which includes every particle within `radius` of `point` on the torus.
+    pub(crate) fn for_each_near(&self, point: RealVec2, radius: Real, mut f: impl FnMut(usize)) {
+        if self.particles.is_empty() {
+            return;
+        }
+        let cell = self.cell_of(&Position(point));
+        let (x, y) = (cell % self.columns, cell / self.columns);
+        let reach = (radius / self.cell_size).ceil();
+        let columns = offsets(reach.x, self.columns);
+        let rows = if self.twisted && columns.clone().count() == self.columns {
+            // Columns reached across the twisted edges are mirrored, so their rows within reach
+            // are elsewhere
+            offsets(Real::INFINITY, self.rows)
+        } else {
+            offsets(reach.y, self.rows)
+        };
+
+        for dy in rows {
+            for dx in columns.clone() {
+                let (x, y) = self.neighbor(x, y, dx, dy);
+                let cell = self.cell_particles(x, y);
+                for &index in cell {
+                    f(index);
+                }
+            }
+        }
+    }
+
+    /// The size of the cells along their shortest side, at least the `min_cell_size` the grid
+    /// was built with.
+    pub(crate) fn min_cell_size(&self) -> Real {
+        self.cell_size.min_element()
+    }
+
+    fn cell_of(&self, position: &Position) -> usize {
+        let cell = (position.0 - self.origin) / self.cell_size;
+        let x = (cell.x.max(0.0) as usize).min(self.columns - 1);
+        let y = (cell.y.max(0.0) as usize).min(self.rows - 1);
+        y * self.columns + x
+    }
+
+    /// Whether moving `dx` cells from the column `x` crosses the left or right edge.
+    fn crosses(&self, x: usize, dx: isize) -> bool {
+        !(0..self.columns as isize).contains(&(x as isize + dx))
+    }
+
+    /// The cell `dx` columns and `dy` rows away from the cell `(x, y)`, mirrored when crossing
+    /// the twisted edges of a Klein bottle.
+    fn neighbor(&self, x: usize, y: usize, dx: isize, dy: isize) -> (usize, usize) {
+        let (neighbor_x, neighbor_y) = (wrap(x, dx, self.columns), wrap(y, dy, self.rows));
+        if self.twisted && self.crosses(x, dx) {
+            (neighbor_x, self.rows - 1 - neighbor_y)
+        } else {
+            (neighbor_x, neighbor_y)
+        }
+    }
+
+    fn cell_particles(&self, x: usize, y: usize) -> &[usize] {
+        let cell = y * self.columns + x;
+        &self.particles[self.cell_starts[cell]..self.cell_starts[cell + 1]]
+    }
+}
+
+/// The number of cells at least `min_cell_size` long fitting along `length`.
+fn cells_along(length: f32, min_cell_size: f32) -> usize {
+    let cells = ((length / min_cell_size) as usize).min(MAX_CELLS_PER_SIDE);
+    // With less than 3 cells, the neighborhood of a cell wraps around onto itself and pairs would
+    // be visited several times.
+    if cells < 3 {
+        1
+    } else {
+        cells
+    }
+}
+
+/// The offsets of the cells within `reach` cells along an axis of `cells` cells, each cell being
+/// reached once even when the reach wraps around the whole axis.
+fn offsets(reach: Real, cells: usize) -> std::ops::RangeInclusive<isize> {
+    let reach = reach.max(0.0) as usize;
+    if reach.saturating_mul(2).saturating_add(1) >= cells {
+        0..=cells as isize - 1
+    } else {
+        -(reach as isize)..=reach as isize
+    }
+}
+
+fn wrap(coordinate: usize, offset: isize, cells: usize) -> usize {
+    (coordinate as isize + offset).rem_euclid(cells as isize) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use bevy::prelude::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::precision::real;
+
+    #[test]
+    fn for_each_pair_visits_each_pair_once() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let positions: Vec<_> = (0..50)
+            .map(|_| {
+                let (x, y) = (rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+                Position(RealVec2::new(real(x), real(y)))
+            })
+            .collect();
+
+        for topology in [
+            Topology::Torus,
+            Topology::CylinderX,
+            Topology::CylinderY,
+            Topology::Klein,
+        ] {
+            let bounds = WorldBounds {
+                topology,
+                ..default()
+            };
+            // 3 cells along each side, all adjacent to each other
+            let mut grid = NeighborGrid::default();
+            grid.rebuild(&positions, 0.6, &bounds);
+
+            let mut visits = 0;
+            let mut pairs = HashSet::new();
+            grid.for_each_pair(|a, b| {
+                visits += 1;
+                assert_ne!(a, b);
+                assert!(
+                    pairs.insert((a.min(b), a.max(b))),
+                    "{topology:?}: ({a}, {b}) twice"
+                );
+            });
+            let n = positions.len();
+            assert_eq!(visits, n * (n - 1) / 2, "{topology:?}");
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+
+use crate::{ColorAttractions, ParticleColors};
+
+/// Shows the attraction matrix as a grid of colored cells in the top-right corner of the window,
+/// to read it at a glance: the cell in row `i` and column `j` is red when the `i`th color is
+/// attracted by the `j`th color and blue when it is repelled, more intense for stronger
+/// attractions. The rows and columns are headed by the colors of the particles.
+///
+/// With `log_scale`, the intensities grow with the logarithm of the magnitude of the
+/// attractions instead of linearly, so that attractions orders of magnitude weaker than the
+/// strongest one stand out from zero. The attractions themselves are left unchanged.
+///
+/// The grid follows the changes of [`ColorAttractions`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub struct AttractionHeatmap {
+    pub enabled: bool,
+    pub log_scale: bool,
+}
+
+/// With [`AttractionHeatmap::log_scale`], the ratio between the strongest attraction and the
+/// attractions that start to be shown on a logarithmic scale rather than a linear one.
+const LOG_RANGE: f32 = 1000.0;
+
+/// The side of each cell of the grid, in logical pixels.
+const CELL_SIZE: f32 = 16.0;
+
+/// Marks the root node of the grid.
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct HeatmapRoot;
+
+pub(crate) fn build(app: &mut App, heatmap: AttractionHeatmap) {
+    app.insert_resource(heatmap)
+        .add_startup_system(setup_heatmap)
+        .add_system(update_heatmap);
+}
+
+fn setup_heatmap(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(8.0),
+                    top: Val::Px(8.0),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::Column,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        HeatmapRoot,
+    ));
+}
+
+/// Rebuilds the grid whenever the matrix, the colors or the visibility change.
+fn update_heatmap(
+    mut commands: Commands,
+    heatmap: Res<AttractionHeatmap>,
+    color_attractions: Res<ColorAttractions>,
+    colors: Res<ParticleColors>,
+    mut roots: Query<(Entity, &mut Visibility), With<HeatmapRoot>>,
+) {
+    if !(heatmap.is_changed() || color_attractions.is_changed() || colors.is_changed()) {
+        return;
+    }
+
+    // Scale the intensities to the strongest attraction
+    let max_magnitude = color_attractions
+        .0
+        .iter()
+        .flatten()
+        .map(|attraction| attraction.0.abs())
+        .fold(0.0, f32::max);
+
+    for (root, mut visibility) in &mut roots {
+        visibility.is_visible = heatmap.enabled;
+        commands.entity(root).despawn_descendants();
+        if !heatmap.enabled {
+            continue;
+        }
+
+        commands.entity(root).with_children(|root| {
+            // The header row, with an empty corner
+            spawn_row(
+                root,
+                [Color::NONE].into_iter().chain(colors.0.iter().copied()),
+            );
+
+            for (row, &row_color) in color_attractions.0.iter().zip(&colors.0) {
+                let cells = row.iter().map(|attraction| {
+                    let intensity = match (max_magnitude > 0.0, heatmap.log_scale) {
+                        (false, _) => 0.0,
+                        (true, false) => attraction.0.abs() / max_magnitude,
+                        // A symmetric logarithm, linear near zero
+                        (true, true) => {
+                            (attraction.0.abs() / max_magnitude * LOG_RANGE).ln_1p()
+                                / LOG_RANGE.ln_1p()
+                        }
+                    };
+                    if attraction.0 >= 0.0 {
+                        Color::rgb(intensity, 0.0, 0.0)
+                    } else {
+                        Color::rgb(0.0, 0.0, intensity)
+                    }
+                });
+                spawn_row(root, [row_color].into_iter().chain(cells));
+            }
+        });
+    }
+}
+
+fn spawn_row(parent: &mut ChildBuilder, cells: impl IntoIterator<Item = Color>) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|row| {
+            for color in cells {
+                row.spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(CELL_SIZE), Val::Px(CELL_SIZE)),
+                        ..Default::default()
+                    },
+                    background_color: color.into(),
+                    ..Default::default()
+                });
+            }
+        });
+}
@@ -0,0 +1,240 @@
+//! Per-frame closures, to experiment with the particles and the parameters without writing Bevy
+//! systems.
+
+use bevy::prelude::*;
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    recenter, update_transform, AttractionRadius, ColorAttractions, ColorId, Friction, MaxDelta,
+    Particle, ParticleColors, ParticleIndex, ParticleLifePlugin, ParticleLifeSystem, Paused,
+    PeakFraction, Position, SimulationId, SimulationTime, Velocity, WorldBounds,
+};
+
+/// A closure run once per frame by [`ParticleLifePlugin::with_frame_hook`].
+///
+/// The closure runs on a single thread at a time, but not necessarily the same one each frame,
+/// hence the `Send` bound.
+pub type FrameHookFn = Box<dyn FnMut(&mut SimView) + Send>;
+
+/// A [`FrameHookFn`] shared between the clones of the plugin.
+#[derive(Clone)]
+pub struct FrameHook(Arc<Mutex<FrameHookFn>>);
+
+impl fmt::Debug for FrameHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameHook").finish_non_exhaustive()
+    }
+}
+
+/// A particle as seen by a [`FrameHook`].
+#[derive(Debug, Clone, Copy)]
+pub struct ParticleState {
+    /// The position, velocity and color of the particle, which the hook may change.
+    pub particle: Particle,
+    /// The particle written back to, wherever the hook moves the state in the view.
+    entity: Entity,
+    index: ParticleIndex,
+    simulation: SimulationId,
+}
+
+impl ParticleState {
+    pub fn index(&self) -> ParticleIndex {
+        self.index
+    }
+
+    pub fn simulation(&self) -> SimulationId {
+        self.simulation
+    }
+}
+
+/// A copy of the state of the simulation given to [`FrameHook`]s, written back once they return.
+///
+/// Hooks can't keep the view or reach the ECS: they run after the particles move and before they
+/// are drawn, and their changes apply from then on. Positions are wrapped into the world, and
+/// colors out of the palette are ignored.
+#[derive(Debug, Clone)]
+pub struct SimView {
+    particles: Vec<ParticleState>,
+    time: SimulationTime,
+    pub paused: bool,
+    pub attraction_radius: AttractionRadius,
+    pub color_attractions: ColorAttractions,
+    pub peak_fraction: PeakFraction,
+    pub friction: Friction,
+    pub max_delta: MaxDelta,
+}
+
+impl SimView {
+    /// The particles of all simulations, in no particular order.
+    pub fn particles(&self) -> &[ParticleState] {
+        &self.particles
+    }
+
+    /// The particles of all simulations, which may also be reordered: each state is written back
+    /// to its own particle.
+    pub fn particles_mut(&mut self) -> &mut [ParticleState] {
+        &mut self.particles
+    }
+
+    pub fn time(&self) -> SimulationTime {
+        self.time
+    }
+}
+
+impl ParticleLifePlugin {
+    /// Runs `hook` once per frame with a [`SimView`] of the simulation, after the hooks added
+    /// before it.
+    pub fn with_frame_hook(mut self, hook: FrameHookFn) -> Self {
+        self.frame_hooks.push(FrameHook(Arc::new(Mutex::new(hook))));
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Resource)]
+struct FrameHooks(Vec<FrameHook>);
+
+pub(crate) fn build(app: &mut App, frame_hooks: &[FrameHook]) {
+    if frame_hooks.is_empty() {
+        return;
+    }
+
+    app.insert_resource(FrameHooks(frame_hooks.to_vec()))
+        .add_system(
+            run_frame_hooks
+                .after(recenter)
+                .before(update_transform)
+                .before(ParticleLifeSystem::UpdateMaterial),
+        );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_frame_hooks(
+    hooks: Res<FrameHooks>,
+    bounds: Res<WorldBounds>,
+    colors: Res<ParticleColors>,
+    time: Res<SimulationTime>,
+    mut paused: ResMut<Paused>,
+    mut attraction_radius: ResMut<AttractionRadius>,
+    mut color_attractions: ResMut<ColorAttractions>,
+    mut peak_fraction: ResMut<PeakFraction>,
+    mut friction: ResMut<Friction>,
+    mut max_delta: ResMut<MaxDelta>,
+    mut query: Query<(
+        Entity,
+        &mut Position,
+        &mut Velocity,
+        &mut ColorId,
+        &ParticleIndex,
+        &SimulationId,
+    )>,
+) {
+    let mut view = SimView {
+        particles: query
+            .iter()
+            .map(
+                |(entity, &position, &velocity, &color, &index, &simulation)| ParticleState {
+                    particle: Particle {
+                        position,
+                        velocity,
+                        color,
+                    },
+                    entity,
+                    index,
+                    simulation,
+                },
+            )
+            .collect(),
+        time: *time,
+        paused: paused.0,
+        attraction_radius: *attraction_radius,
+        color_attractions: color_attractions.clone(),
+        peak_fraction: *peak_fraction,
+        friction: *friction,
+        max_delta: *max_delta,
+    };
+    for hook in &hooks.0 {
+        // A hook that panicked left no broken state behind, as the view is only a copy
+        let mut hook = hook.0.lock().unwrap_or_else(|error| error.into_inner());
+        hook(&mut view);
+    }
+
+    // Only write back what changed, to keep change detection meaningful
+    for state in &view.particles {
+        let Ok((_, mut position, mut velocity, mut color, ..)) = query.get_mut(state.entity) else {
+            continue;
+        };
+        let particle = state.particle;
+        let (new_position, new_velocity) =
+            bounds.wrap_motion(particle.position.0, particle.velocity.0);
+        if position.0 != new_position {
+            position.0 = new_position;
+        }
+        if velocity.0 != new_velocity {
+            velocity.0 = new_velocity;
+        }
+        if *color != particle.color && particle.color.0 < colors.0.len() {
+            *color = particle.color;
+        }
+    }
+    if paused.0 != view.paused {
+        paused.0 = view.paused;
+    }
+    if *attraction_radius != view.attraction_radius {
+        *attraction_radius = view.attraction_radius;
+    }
+    if *color_attractions != view.color_attractions {
+        *color_attractions = view.color_attractions;
+    }
+    if *peak_fraction != view.peak_fraction {
+        *peak_fraction = view.peak_fraction;
+    }
+    if *friction != view.friction {
+        *friction = view.friction;
+    }
+    if *max_delta != view.max_delta {
+        *max_delta = view.max_delta;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        advance_steps,
+        clock::{headless_app, test_plugin},
+        precision::real_vec2,
+    };
+
+    #[test]
+    fn reordering_the_view_keeps_each_particle_its_state() {
+        let initial_particles: Vec<_> = (0..4)
+            .map(|i| Particle {
+                position: Position(real_vec2(Vec2::new(0.1 * i as f32, 0.0))),
+                velocity: Velocity(real_vec2(Vec2::new(0.0, 0.1 * i as f32))),
+                color: ColorId(i % 2),
+            })
+            .collect();
+        let mut app = headless_app(
+            test_plugin(initial_particles.clone())
+                .with_frame_hook(Box::new(|view| view.particles_mut().reverse())),
+        );
+        app.insert_resource(Paused(true));
+        advance_steps(&mut app, 0.01, 3);
+
+        let mut query = app
+            .world
+            .query::<(&Position, &Velocity, &ColorId, &ParticleIndex)>();
+        for (&position, &velocity, &color, index) in query.iter(&app.world) {
+            let particle = Particle {
+                position,
+                velocity,
+                color,
+            };
+            assert_eq!(particle, initial_particles[index.0 as usize]);
+        }
+    }
+}
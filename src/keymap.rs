@@ -0,0 +1,245 @@
+//! Keyboard shortcuts for the interactive features.
+//!
+//! Every shortcut goes through the [`KeyMap`] resource rather than each feature reading the
+//! keyboard on its own, so that features can't conflict over keys and users can rebind them all in
+//! one place.
+
+use bevy::{ecs::system::SystemParam, input::InputSystem, prelude::*, utils::HashMap};
+
+use std::{error::Error, fmt, marker::PhantomData};
+
+use crate::{
+    logging::LOG_TARGET, replay::Replayer, update_simulation_time, AttractionHeatmap,
+    BondRendering, ColorId, FlowField, PaintBrush, ParticleColors, Paused, PerfOverlay,
+    RecenterConfig, ResetParticles, ReverseTime, ShuffleColors, StepSimulation, TakeScreenshot,
+    TrailBuffer, VelocityTicks,
+};
+
+/// An interactive feature that can be triggered from the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    /// Toggles [`Paused`].
+    Pause,
+    /// Sends [`StepSimulation`].
+    Step,
+    /// Toggles [`ReverseTime`].
+    ReverseTime,
+    /// Sends [`ShuffleColors`], keeping the number of particles of each color.
+    ShuffleColors,
+    /// Sends [`ResetParticles`].
+    Reset,
+    /// Sends [`TakeScreenshot`].
+    Screenshot,
+    /// Toggles [`PerfOverlay::enabled`].
+    TogglePerfOverlay,
+    /// Toggles [`BondRendering::enabled`].
+    ToggleBonds,
+    /// Toggles [`RecenterConfig::enabled`].
+    ToggleRecenter,
+    /// Toggles [`AttractionHeatmap::enabled`].
+    ToggleHeatmap,
+    /// Toggles [`FlowField::enabled`].
+    ToggleFlowField,
+    /// Toggles [`VelocityTicks::enabled`].
+    ToggleVelocityTicks,
+    /// Toggles [`TrailBuffer::enabled`].
+    ToggleTrails,
+    /// Toggles [`PaintBrush::enabled`].
+    TogglePaint,
+    /// Sets [`PaintBrush::color`] to the given color, if it exists.
+    SelectPaintColor(usize),
+}
+
+/// The key bound to each [`Action`]. Actions missing from the map have no shortcut.
+///
+/// Each key may be bound to at most one action, see [`KeyMap::validate`].
+#[derive(Debug, Clone, PartialEq, Resource)]
+pub struct KeyMap(pub HashMap<Action, KeyCode>);
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        const COLOR_KEYS: [KeyCode; 9] = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+        ];
+        let select_colors = COLOR_KEYS
+            .into_iter()
+            .enumerate()
+            .map(|(color, key)| (Action::SelectPaintColor(color), key));
+
+        let actions = [
+            (Action::Pause, KeyCode::Space),
+            (Action::Step, KeyCode::Period),
+            (Action::ReverseTime, KeyCode::T),
+            (Action::ShuffleColors, KeyCode::S),
+            (Action::Reset, KeyCode::Back),
+            (Action::Screenshot, KeyCode::F12),
+            (Action::TogglePerfOverlay, KeyCode::F3),
+            (Action::ToggleBonds, KeyCode::B),
+            (Action::ToggleRecenter, KeyCode::R),
+            (Action::ToggleHeatmap, KeyCode::M),
+            (Action::ToggleFlowField, KeyCode::F),
+            (Action::ToggleVelocityTicks, KeyCode::V),
+            (Action::ToggleTrails, KeyCode::L),
+            (Action::TogglePaint, KeyCode::P),
+        ];
+        Self(actions.into_iter().chain(select_colors).collect())
+    }
+}
+
+impl KeyMap {
+    /// Checks that no key is bound to several actions.
+    pub fn validate(&self) -> Result<(), KeyMapError> {
+        let mut actions_by_key = HashMap::<KeyCode, Vec<Action>>::default();
+        for (&action, &key) in &self.0 {
+            actions_by_key.entry(key).or_default().push(action);
+        }
+
+        // Report the smallest duplicate key so that the error doesn't depend on the hash order
+        let duplicate = actions_by_key
+            .into_iter()
+            .filter(|(_, actions)| actions.len() > 1)
+            .min_by_key(|&(key, _)| key);
+        match duplicate {
+            Some((key, mut actions)) => {
+                actions.sort();
+                Err(KeyMapError::DuplicateBinding { key, actions })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// The actions whose key was pressed this frame.
+    fn just_pressed<'a>(&'a self, input: &'a Input<KeyCode>) -> impl Iterator<Item = Action> + 'a {
+        self.0
+            .iter()
+            .filter(|&(_, &key)| input.just_pressed(key))
+            .map(|(&action, _)| action)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyMapError {
+    /// `key` is bound to all of `actions`.
+    DuplicateBinding { key: KeyCode, actions: Vec<Action> },
+}
+
+impl fmt::Display for KeyMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateBinding { key, actions } => {
+                write!(f, "{key:?} is bound to several actions: {actions:?}")
+            }
+        }
+    }
+}
+
+impl Error for KeyMapError {}
+
+pub(crate) fn build(app: &mut App, keymap: KeyMap) {
+    if let Err(error) = keymap.validate() {
+        panic!("invalid key map: {error}");
+    }
+
+    app.insert_resource(keymap)
+        .add_system(validate_keymap)
+        .add_system_to_stage(
+            CoreStage::PreUpdate,
+            handle_shortcuts
+                .after(InputSystem)
+                .before(update_simulation_time),
+        );
+}
+
+/// Warns about duplicate bindings introduced by editing the [`KeyMap`] at runtime.
+fn validate_keymap(keymap: Res<KeyMap>) {
+    if !keymap.is_changed() || keymap.is_added() {
+        return;
+    }
+    if let Err(error) = keymap.validate() {
+        warn!(target: LOG_TARGET, event = "invalid_keymap", %error);
+    }
+}
+
+/// The overlays toggled by the shortcuts.
+#[derive(SystemParam)]
+pub(crate) struct Overlays<'w, 's> {
+    perf_overlay: ResMut<'w, PerfOverlay>,
+    bond_rendering: ResMut<'w, BondRendering>,
+    heatmap: ResMut<'w, AttractionHeatmap>,
+    flow_field: ResMut<'w, FlowField>,
+    velocity_ticks: ResMut<'w, VelocityTicks>,
+    trail_buffer: ResMut<'w, TrailBuffer>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+/// The events sent by the shortcuts.
+#[derive(SystemParam)]
+pub(crate) struct ShortcutEvents<'w, 's> {
+    steps: EventWriter<'w, 's, StepSimulation>,
+    shuffles: EventWriter<'w, 's, ShuffleColors>,
+    resets: EventWriter<'w, 's, ResetParticles>,
+    screenshots: EventWriter<'w, 's, TakeScreenshot>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handle_shortcuts(
+    keymap: Res<KeyMap>,
+    input: Option<Res<Input<KeyCode>>>,
+    replayer: Option<Res<Replayer>>,
+    mut paused: ResMut<Paused>,
+    mut reverse_time: ResMut<ReverseTime>,
+    mut recenter: ResMut<RecenterConfig>,
+    mut overlays: Overlays,
+    mut paint_brush: ResMut<PaintBrush>,
+    colors: Res<ParticleColors>,
+    mut events: ShortcutEvents,
+) {
+    // Headless apps have no keyboard, and replays are driven by their log only
+    let Some(input) = input else {
+        return;
+    };
+    if replayer.is_some() {
+        return;
+    }
+
+    for action in keymap.just_pressed(&input) {
+        match action {
+            Action::Pause => paused.0 = !paused.0,
+            Action::Step => events.steps.send(StepSimulation),
+            Action::ReverseTime => reverse_time.0 = !reverse_time.0,
+            Action::ShuffleColors => events.shuffles.send(ShuffleColors {
+                preserve_counts: true,
+            }),
+            Action::Reset => events.resets.send(ResetParticles),
+            Action::Screenshot => events.screenshots.send(TakeScreenshot),
+            Action::TogglePerfOverlay => {
+                overlays.perf_overlay.enabled = !overlays.perf_overlay.enabled
+            }
+            Action::ToggleBonds => {
+                overlays.bond_rendering.enabled = !overlays.bond_rendering.enabled
+            }
+            Action::ToggleRecenter => recenter.enabled = !recenter.enabled,
+            Action::ToggleHeatmap => overlays.heatmap.enabled = !overlays.heatmap.enabled,
+            Action::ToggleFlowField => overlays.flow_field.enabled = !overlays.flow_field.enabled,
+            Action::ToggleVelocityTicks => {
+                overlays.velocity_ticks.enabled = !overlays.velocity_ticks.enabled
+            }
+            Action::ToggleTrails => overlays.trail_buffer.enabled = !overlays.trail_buffer.enabled,
+            Action::TogglePaint => paint_brush.enabled = !paint_brush.enabled,
+            Action::SelectPaintColor(color) => {
+                if color < colors.0.len() {
+                    paint_brush.color = ColorId(color);
+                }
+            }
+        }
+    }
+}
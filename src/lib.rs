@@ -1,89 +1,876 @@
 use bevy::{
+    diagnostic::Diagnostics,
+    ecs::{system::SystemParam, world::EntityMut},
     prelude::{shape::Circle, *},
-    render::camera::ScalingMode,
+    render::view::RenderLayers,
     sprite::Mesh2dHandle,
+    tasks::{ComputeTaskPool, ParallelSlice},
+    utils::HashMap,
 };
+use rand::{rngs::StdRng, SeedableRng};
+
+use std::{collections::VecDeque, marker::PhantomData};
+
+mod affinity;
+mod background;
+mod bonds;
+mod bounds;
+mod camera;
+mod clock;
+mod config;
+mod error;
+mod flow;
+mod friction;
+mod grid;
+mod heatmap;
+mod hook;
+mod keymap;
+mod lifespan;
+mod lines;
+mod logging;
+mod matrix_image;
+mod neighbors;
+mod paint;
+mod perf;
+mod precision;
+mod probe;
+mod reactions;
+mod recipe;
+mod replay;
+mod reset;
+mod seam;
+mod select;
+mod shuffle;
+mod simulations;
+mod snapshot;
+mod spawn;
+mod svg;
+mod ticks;
+mod trails;
+mod validation;
+
+pub use affinity::{affinity_graph, export_affinity_csv};
+pub use bonds::BondRendering;
+pub use bounds::{toroidal_delta, toroidal_dist, Topology, WorldBounds};
+pub use camera::CameraTarget;
+pub use clock::{advance, advance_steps};
+pub use config::{load_config_with_overrides, ConfigFileError, PartialConfig};
+pub use error::{DimensionMismatch, ParseError, ParticleLifeError};
+pub use flow::FlowField;
+pub use friction::{Friction, SettlePhase};
+pub use heatmap::AttractionHeatmap;
+pub use hook::{FrameHook, FrameHookFn, ParticleState, SimView};
+pub use keymap::{Action, KeyMap, KeyMapError};
+pub use lifespan::{Age, Lifespan};
+pub use matrix_image::{
+    attractions_from_image, attractions_to_image, AttractionImageError, AttractionRange,
+};
+pub use neighbors::ParticleNeighbors;
+pub use paint::PaintBrush;
+pub use perf::{PerfOverlay, PHYSICS_TIME};
+pub use precision::{Real, RealVec2};
+pub use probe::{ProbeConfig, ProbeRecord, ProbeSample, ProbedEntity};
+pub use reactions::{Reaction, Reactions};
+pub use recipe::{load_recipe, save_recipe, Recipe};
+pub use replay::{ReplayEvent, ReplayLog, ReplayLogError, ReplayMode};
+pub use reset::ResetParticles;
+pub use seam::SeamHighlight;
+pub use select::Selection;
+pub use shuffle::ShuffleColors;
+pub use simulations::{ExtraSimulation, SimulationId, SimulationOverrides, SimulationSettings};
+pub use snapshot::{SimulationSnapshot, SnapshotError};
+pub use spawn::{
+    spawn_from_density, spawn_gaussian, spawn_in_disc, spawn_in_rect, spawn_in_ring, spawn_poisson,
+    ParticleSet,
+};
+pub use svg::{export_svg, to_svg, TakeScreenshot};
+pub use ticks::VelocityTicks;
+pub use trails::{TrailBuffer, TrailMode};
+pub use validation::{ConfigError, ConfigProblem};
+
+use grid::NeighborGrid;
+use perf::PhysicsTimer;
+use precision::{real, real_vec2, single, single_vec2};
+use replay::FixedTimeStep;
 
 #[derive(Debug, Clone, Default)]
 pub struct ParticleLifePlugin {
+    /// The particles of the main simulation to spawn, in order: the `i`th particle is given
+    /// `ParticleIndex(i)`.
     pub initial_particles: Vec<Particle>,
+    /// Simulations to run alongside the main one, each in its own viewport. The `i`th one is given
+    /// `SimulationId(i + 1)`, and its particles are numbered after the particles of the previous
+    /// simulations.
+    pub extra_simulations: Vec<ExtraSimulation>,
+    /// The world of all simulations, which should contain the initial particles.
+    pub bounds: WorldBounds,
     pub colors: Vec<Color>,
+    pub mesh: ParticleMesh,
     pub color_attractions: ColorAttractions,
     pub attraction_radius: AttractionRadius,
+    pub color_radius_scale: ColorRadiusScale,
+    pub peak_fraction: PeakFraction,
+    pub kernel: Kernel,
+    pub smooth_cutoff: SmoothCutoff,
+    pub anisotropy: Anisotropy,
+    pub bond_rendering: BondRendering,
+    pub seam_highlight: SeamHighlight,
+    pub max_delta: MaxDelta,
+    pub adaptive_timestep: Option<AdaptiveTimestep>,
+    pub force_computation: ForceComputation,
+    pub compute_budget: ComputeBudget,
+    pub recenter: RecenterConfig,
+    pub containment: Containment,
+    pub quorum_sensing: Option<QuorumSensing>,
+    pub friction: Friction,
+    pub settle_phase: Option<SettlePhase>,
+    /// Seeds [`SimRng`]. When `None`, it is seeded from system entropy. Replays use the seed of
+    /// their log instead.
+    pub seed: Option<u64>,
+    pub lifespan: Option<Lifespan>,
+    pub reactions: Reactions,
+    pub perf_overlay: PerfOverlay,
+    pub heatmap: AttractionHeatmap,
+    pub flow_field: FlowField,
+    pub velocity_ticks: VelocityTicks,
+    pub trail_buffer: TrailBuffer,
+    pub keymap: KeyMap,
+    pub paint_brush: PaintBrush,
+    pub structural_change_limit: StructuralChangeLimit,
+    pub replay: ReplayMode,
+    pub probe: ProbeConfig,
+    /// Closures run once per frame, added with [`Self::with_frame_hook`].
+    pub frame_hooks: Vec<FrameHook>,
+    /// Runs the simulation without rendering it, for tests and servers: the cameras, the drawing
+    /// of the particles, the overlays and the mouse and keyboard controls are left out, so that
+    /// the app only needs the [`MinimalPlugins`].
+    pub headless: bool,
+}
+
+impl ParticleLifePlugin {
+    /// Creates a plugin from its main settings, the others being the defaults, checking that they
+    /// are consistent with [`Self::validate`].
+    pub fn try_new(
+        initial_particles: Vec<Particle>,
+        colors: Vec<Color>,
+        color_attractions: ColorAttractions,
+        attraction_radius: AttractionRadius,
+    ) -> Result<Self, ParticleLifeError> {
+        let plugin = Self {
+            initial_particles,
+            colors,
+            color_attractions,
+            attraction_radius,
+            ..Default::default()
+        };
+        plugin.validate()?;
+        Ok(plugin)
+    }
+
+    /// Creates a plugin for a palette of `N` colors known at compile time, so that the number of
+    /// colors and the dimensions of the attraction matrix are checked to match by the compiler.
+    pub fn from_static<const N: usize>(
+        initial_particles: Vec<Particle>,
+        colors: [Color; N],
+        color_attractions: ColorAttractionsN<N>,
+        attraction_radius: AttractionRadius,
+    ) -> Self {
+        Self {
+            initial_particles,
+            colors: colors.into(),
+            color_attractions: color_attractions.into(),
+            attraction_radius,
+            ..Default::default()
+        }
+    }
 }
 
 impl Plugin for ParticleLifePlugin {
     fn build(&self, app: &mut App) {
-        app.world
-            .spawn_batch(self.initial_particles.iter().copied());
+        if let Err(error) = self.validate() {
+            panic!("invalid particle life configuration: {error}");
+        }
+
+        simulations::build(app, &self.extra_simulations);
+
+        let mut next_index = 0..;
+        app.world.spawn_batch(
+            self.initial_particles
+                .iter()
+                .zip(&mut next_index)
+                .map(|(&particle, index)| {
+                    (particle, ParticleIndex(index), Age(0.0), SimulationId::MAIN)
+                })
+                .collect::<Vec<_>>(),
+        );
+        for (simulation, id) in self.extra_simulations.iter().zip(1..) {
+            app.world.spawn_batch(
+                simulation
+                    .initial_particles
+                    .iter()
+                    .zip(&mut next_index)
+                    .map(|(&particle, index)| {
+                        (
+                            particle,
+                            ParticleIndex(index),
+                            Age(0.0),
+                            SimulationId(id),
+                            RenderLayers::layer(id as u8),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            );
+        }
+        app.insert_resource(NextParticleIndex(next_index.start))
+            .insert_resource(self.structural_change_limit)
+            .init_resource::<PendingChanges>()
+            .add_event::<SpawnParticle>()
+            .add_event::<DespawnParticle>()
+            .add_system_to_stage(CoreStage::PreUpdate, apply_structural_changes);
+        reset::build(app, self.initial_spawns());
+
+        // The seed is drawn explicitly so that recordings can store it
+        let seed = match &self.replay {
+            ReplayMode::Replay(log) => log.seed,
+            _ => self.seed.unwrap_or_else(rand::random),
+        };
+        app.insert_resource(SimRng(StdRng::seed_from_u64(seed)));
+        replay::build(app, &self.replay, seed);
 
         app.insert_resource(self.color_attractions.clone())
-            .insert_resource(self.attraction_radius);
+            .insert_resource(self.attraction_radius)
+            .insert_resource(self.color_radius_scale.clone())
+            .insert_resource(self.peak_fraction)
+            .insert_resource(self.kernel)
+            .insert_resource(self.smooth_cutoff)
+            .insert_resource(self.anisotropy)
+            .insert_resource(self.containment)
+            .insert_resource(self.compute_budget)
+            .insert_resource(self.bounds);
+        if let Some(quorum_sensing) = self.quorum_sensing {
+            app.insert_resource(quorum_sensing);
+        }
 
-        app.add_startup_system(setup_camera);
+        app.insert_resource(ParticleColors(self.colors.clone()));
 
-        app.insert_resource(ParticleColors(self.colors.clone()))
-            .init_resource::<ColorHandles>()
-            .add_startup_system(setup_color_materials);
+        if let Some(adaptive_timestep) = self.adaptive_timestep {
+            app.insert_resource(adaptive_timestep);
+        }
+        app.insert_resource(self.max_delta)
+            .init_resource::<SimulationTime>()
+            .init_resource::<Paused>()
+            .init_resource::<ReverseTime>()
+            .add_event::<StepSimulation>()
+            .add_system_to_stage(CoreStage::PreUpdate, update_simulation_time);
 
-        app.init_resource::<MeshHandle>()
-            .add_startup_system(setup_mesh);
+        app.insert_resource(self.force_computation)
+            .add_system_to_stage(CoreStage::PreUpdate, insert_acceleration)
+            .add_system(update_position)
+            .add_system(update_velocity.after(update_position))
+            .add_system(background::update_velocity_in_background.after(update_position))
+            .insert_resource(self.recenter)
+            .add_system(
+                recenter
+                    .after(update_velocity)
+                    .after(background::update_velocity_in_background),
+            );
 
-        app.add_startup_system(
-            setup_mesh_and_color
-                .after(setup_color_materials)
-                .after(setup_mesh),
+        friction::build(app, self.friction, self.settle_phase);
+        lifespan::build(app, self.lifespan);
+        reactions::build(app, self.reactions.clone());
+        neighbors::build(app);
+        shuffle::build(app);
+        svg::build(app);
+
+        logging::build(app);
+        probe::build(app, self.probe.clone());
+        hook::build(app, &self.frame_hooks);
+
+        if !self.headless {
+            self.build_rendering(app);
+        }
+    }
+}
+
+impl ParticleLifePlugin {
+    /// Sets up the cameras, the drawing of the particles, the overlays and the mouse and keyboard
+    /// controls.
+    fn build_rendering(&self, app: &mut App) {
+        camera::build(app, 1 + self.extra_simulations.len());
+
+        app.init_resource::<ColorHandles>()
+            .add_startup_system(setup_color_materials);
+
+        let mesh = self.mesh.clone();
+        app.init_resource::<MeshHandle>().add_startup_system(
+            move |meshes: ResMut<_>, handle: ResMut<_>| setup_mesh(meshes, handle, mesh.clone()),
         );
 
-        app.add_system(update_position)
-            .add_system(update_velocity)
+        app.add_system(insert_mesh_and_color)
             .add_system(update_transform)
-            .add_system(update_material);
+            .add_system(update_material.label(ParticleLifeSystem::UpdateMaterial));
+
+        lifespan::build_fading(app);
+        seam::build(app, self.seam_highlight);
+        perf::build(app, self.perf_overlay);
+        heatmap::build(app, self.heatmap);
+        flow::build(app, self.flow_field);
+        ticks::build(app, self.velocity_ticks);
+        trails::build(app, self.trail_buffer);
+        keymap::build(app, self.keymap.clone());
+        paint::build(app, self.paint_brush);
+        select::build(app);
+
+        lines::build(app);
+        app.insert_resource(self.bond_rendering)
+            .add_system(bonds::draw_bonds);
+    }
+
+    /// The initial particles of every simulation, as the events that would spawn them.
+    fn initial_spawns(&self) -> Vec<SpawnParticle> {
+        let main = self
+            .initial_particles
+            .iter()
+            .map(|&particle| SpawnParticle {
+                particle,
+                simulation: SimulationId::MAIN,
+            });
+        let extra = self
+            .extra_simulations
+            .iter()
+            .zip(1..)
+            .flat_map(|(simulation, id)| {
+                simulation
+                    .initial_particles
+                    .iter()
+                    .map(move |&particle| SpawnParticle {
+                        particle,
+                        simulation: SimulationId(id),
+                    })
+            });
+        main.chain(extra).collect()
     }
 }
 
-#[derive(Debug, Clone, Copy, Bundle)]
+#[derive(Debug, Clone, Copy, PartialEq, Bundle)]
 pub struct Particle {
     pub position: Position,
     pub velocity: Velocity,
     pub color: ColorId,
 }
 
-#[derive(Debug, Clone, Copy, Default, Component)]
-pub struct Position(pub Vec2);
+/// Send this event to spawn a particle while the app runs, numbered after all the particles
+/// spawned before it and rendered like the initial particles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpawnParticle {
+    pub particle: Particle,
+    pub simulation: SimulationId,
+}
+
+/// Send this event to despawn a particle while the app runs. Particles already despawned are
+/// ignored.
+#[derive(Debug, Clone, Copy)]
+pub struct DespawnParticle(pub Entity);
 
+/// Limits how many particles [`SpawnParticle`] and [`DespawnParticle`] events spawn and despawn
+/// each frame, to smooth out the hitches of large bursts.
+///
+/// With `max_structural_changes_per_frame`, the changes beyond the limit are queued and applied
+/// over the next frames, in the order they were sent, a frame's spawns coming before its
+/// despawns. Particles spawned with [`spawn_particles_with`] aren't limited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub struct StructuralChangeLimit {
+    pub max_structural_changes_per_frame: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum StructuralChange {
+    Spawn(SpawnParticle),
+    Despawn(Entity),
+}
+
+/// The changes left to apply because of the [`StructuralChangeLimit`], oldest first.
+#[derive(Debug, Clone, Default, Resource)]
+struct PendingChanges(VecDeque<StructuralChange>);
+
+/// The [`ParticleIndex`] of the next particle to spawn.
+#[derive(Debug, Clone, Copy, Resource)]
+struct NextParticleIndex(u32);
+
+/// Spawns `particles` into `simulation` like [`SpawnParticle`] does, then calls `f` on each of
+/// them, for instance to insert components of your own alongside the components of the plugin.
+///
+/// The particles are spawned and numbered when `commands` are applied.
+pub fn spawn_particles_with(
+    commands: &mut Commands,
+    particles: impl IntoIterator<Item = Particle>,
+    simulation: SimulationId,
+    mut f: impl FnMut(&mut EntityMut) + Send + Sync + 'static,
+) {
+    let particles: Vec<_> = particles.into_iter().collect();
+    commands.add(move |world: &mut World| {
+        for particle in particles {
+            let mut next_index = world.resource_mut::<NextParticleIndex>();
+            let index = ParticleIndex(next_index.0);
+            next_index.0 += 1;
+
+            let mut entity = world.spawn((particle, index, Age(0.0), simulation));
+            if simulation != SimulationId::MAIN {
+                entity.insert(RenderLayers::layer(simulation.0 as u8));
+            }
+            f(&mut entity);
+        }
+    });
+}
+
+fn apply_structural_changes(
+    mut commands: Commands,
+    limit: Res<StructuralChangeLimit>,
+    mut pending: ResMut<PendingChanges>,
+    mut spawns: EventReader<SpawnParticle>,
+    mut despawns: EventReader<DespawnParticle>,
+) {
+    pending
+        .0
+        .extend(spawns.iter().map(|&spawn| StructuralChange::Spawn(spawn)));
+    pending.0.extend(
+        despawns
+            .iter()
+            .map(|&DespawnParticle(entity)| StructuralChange::Despawn(entity)),
+    );
+
+    let count = match limit.max_structural_changes_per_frame {
+        Some(max) => max.min(pending.0.len()),
+        None => pending.0.len(),
+    };
+    for change in pending.0.drain(..count) {
+        match change {
+            StructuralChange::Spawn(SpawnParticle {
+                particle,
+                simulation,
+            }) => spawn_particles_with(&mut commands, [particle], simulation, |_| {}),
+            StructuralChange::Despawn(entity) => {
+                if let Some(mut entity) = commands.get_entity(entity) {
+                    entity.despawn();
+                }
+            }
+        }
+    }
+}
+
+/// A stable identity for a particle, independent of how Bevy allocates entities.
+///
+/// Particles are numbered in the order they are spawned, starting from `0`, so two runs with the
+/// same initial particles assign the same indices. Use it rather than [`Entity`] to refer to
+/// particles in exports and snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
+pub struct ParticleIndex(pub u32);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Component)]
+pub struct Position(pub RealVec2);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Component)]
+pub struct Velocity(pub RealVec2);
+
+/// The acceleration of a particle at its current position, computed at the end of the previous
+/// step and used for the first half of the velocity Verlet step.
 #[derive(Debug, Clone, Copy, Default, Component)]
-pub struct Velocity(pub Vec2);
+struct Acceleration(RealVec2);
 
-#[derive(Debug, Clone, Copy, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
 pub struct ColorId(pub usize);
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Attraction(pub f32);
 
-#[derive(Debug, Clone, Copy, Default, Resource)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Resource)]
 pub struct AttractionRadius {
     pub rmin: f32,
     pub rmax: f32,
 }
 
+/// Scales the [`AttractionRadius`] per color: particles with the `i`th color feel other particles
+/// with `rmin` and `rmax` multiplied by `self.0[i]`, so that some colors react from farther away
+/// than others. Colors without an entry have a scale of `1`.
+///
+/// The scale applies on top of the attraction radius of the simulation, whether it is the global
+/// one or the one from [`SimulationSettings::attraction_radius`].
+#[derive(Debug, Clone, Default, PartialEq, Resource)]
+pub struct ColorRadiusScale(pub Vec<f32>);
+
+impl ColorRadiusScale {
+    /// The scale of the radii felt by particles with the color `color`.
+    pub fn get(&self, color: ColorId) -> f32 {
+        self.0.get(color.0).copied().unwrap_or(1.0)
+    }
+
+    /// The largest scale of any color, which is at least `1` since colors without an entry have a
+    /// scale of `1`.
+    fn max(&self) -> f32 {
+        self.0.iter().copied().fold(1.0, f32::max)
+    }
+}
+
+/// Labels of the systems of the plugin that other systems need to be ordered against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemLabel)]
+enum ParticleLifeSystem {
+    UpdateMaterial,
+}
+
+/// The random number generator used by the simulation at runtime, seeded from
+/// [`ParticleLifePlugin::seed`] so that runs can be reproduced.
+#[derive(Debug, Clone, Resource)]
+pub struct SimRng(pub StdRng);
+
+/// Where the attraction between `rmin` and `rmax` peaks, as a fraction of the way from `rmin` to
+/// `rmax`. Values below `0.5` make the attraction peak closer, values above `0.5` farther.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct PeakFraction(pub f32);
+
+impl Default for PeakFraction {
+    fn default() -> Self {
+        Self(0.5)
+    }
+}
+
+/// The profile of the attraction between `rmin` and `rmax`, which is `0` at `rmin`, rises to the
+/// appropriate entry of [`ColorAttractions`] at the peak distance (see [`PeakFraction`]) and falls
+/// back to `0` at `rmax`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub enum Kernel {
+    /// Rises and falls linearly. The derivative of the force is discontinuous at `rmin`, `rmax`
+    /// and the peak, which can make particles look jittery.
+    #[default]
+    Tent,
+    /// Eases in and out with a smoothstep curve on both sides of the peak, so that the derivative
+    /// of the force is continuous.
+    Smoothstep,
+    /// A bell curve centered on the peak, reaching about 1% of the peak at `rmin` and `rmax`.
+    Gaussian,
+}
+
+impl Kernel {
+    /// Shapes `t`, the position relative to the peak on a linear tent: `0` at `rmin` or `rmax`
+    /// and `1` at the peak.
+    fn shape(self, t: Real) -> Real {
+        match self {
+            Kernel::Tent => t,
+            Kernel::Smoothstep => t * t * (3.0 - 2.0 * t),
+            // Each side of the peak spans 3 standard deviations
+            Kernel::Gaussian => Real::exp(-4.5 * (1.0 - t) * (1.0 - t)),
+        }
+    }
+}
+
+/// Fades the attraction out over the last `window` fraction of the way from `rmin` to `rmax`, so
+/// that both the force and its derivative reach `0` at `rmax`. Otherwise, particles entering or
+/// leaving the range of each other see their acceleration change direction abruptly, which shows
+/// as popping with [`Kernel::Tent`].
+///
+/// A `window` of `0` cuts the attraction off at `rmax` as is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Resource)]
+pub struct SmoothCutoff {
+    pub window: f32,
+}
+
+impl SmoothCutoff {
+    /// The factor the attraction is multiplied by at `distance`, in `rmin..=rmax`.
+    fn ramp(self, distance: Real, rmin: Real, rmax: Real) -> Real {
+        let window = real(self.window.clamp(0.0, 1.0)) * (rmax - rmin);
+        if window <= 0.0 {
+            return 1.0;
+        }
+        // Smoothstep from `0` at `rmax` to `1` at the start of the window
+        let t = ((rmax - distance) / window).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+/// Upper bound on the time step used to advance the simulation, in seconds.
+///
+/// Frames that take longer than this, such as the first frame or a frame after a hitch, only
+/// advance the simulation by `MaxDelta`, so a long frame can't launch particles across the world.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct MaxDelta(pub f32);
+
+impl Default for MaxDelta {
+    fn default() -> Self {
+        Self(1.0 / 30.0)
+    }
+}
+
+/// When present, advances the running simulation by a time step chosen each frame so that the
+/// fastest particle moves by at most `max_displacement`, clamped to `[min_dt, max_dt]`, instead
+/// of by the frame time. The time step shrinks during bursts to avoid particles tunneling through
+/// each other, and grows back once they calm down, regardless of [`MaxDelta`].
+///
+/// The time step then depends on the velocities of the particles, so runs are only reproducible
+/// with a fixed [`ParticleLifePlugin::seed`].
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct AdaptiveTimestep {
+    pub min_dt: f32,
+    pub max_dt: f32,
+    pub max_displacement: f32,
+}
+
+impl Default for AdaptiveTimestep {
+    fn default() -> Self {
+        Self {
+            min_dt: 1.0 / 240.0,
+            max_dt: 1.0 / 30.0,
+            max_displacement: 0.005,
+        }
+    }
+}
+
+impl AdaptiveTimestep {
+    /// The time step for particles moving at most at `max_speed`.
+    ///
+    /// Bounds that [`ParticleLifePlugin::validate`] rejects, such as `min_dt > max_dt`, give
+    /// `max_dt` priority rather than panicking, as they may still be set at runtime.
+    pub fn delta(&self, max_speed: f32) -> f32 {
+        if max_speed > 0.0 {
+            (self.max_displacement / max_speed)
+                .max(self.min_dt)
+                .min(self.max_dt)
+        } else {
+            self.max_dt
+        }
+    }
+}
+
+/// The simulation clock, advanced once per frame before the physics systems run.
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub struct SimulationTime {
+    /// The time step the physics systems integrate with this frame, in seconds.
+    pub delta: f32,
+    /// The total simulated time, in seconds.
+    pub elapsed: f32,
+}
+
+/// Whether the simulation is paused. While paused, the [`SimulationTime`] doesn't advance, except
+/// by [`StepSimulation`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub struct Paused(pub bool);
+
+/// Send this event to advance a [`Paused`] simulation by one time step of [`MaxDelta`]. It has no
+/// effect while the simulation runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepSimulation;
+
+/// Runs the simulation backward in time when `true`, by negating the time step the positions and
+/// velocities are integrated with. Toggling it mid-run makes the particles approximately retrace
+/// their path.
+///
+/// With [`ForceComputation::Immediate`] or [`ForceComputation::Parallel`], particles are
+/// integrated with velocity Verlet, which is time-reversible: going back the same number of frames
+/// with the same time steps returns to the same state, up to floating-point errors. Anything that
+/// isn't a force between particles breaks this, such as [`Friction`], [`SettlePhase`],
+/// [`RecenterConfig`], [`Lifespan`] or the [`MaxDelta`] clamping varying frame times, as does
+/// [`ForceComputation::Background`]. The [`SimulationTime`] keeps counting forward.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub struct ReverseTime(pub bool);
+
+impl ReverseTime {
+    /// The sign of the time step the particles are integrated with.
+    fn direction(self) -> Real {
+        if self.0 {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+}
+
+/// How the forces between particles are computed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub enum ForceComputation {
+    /// Forces are computed and applied every frame, blocking the schedule until done.
+    #[default]
+    Immediate,
+    /// Forces are computed on the [`AsyncComputeTaskPool`] from a snapshot of the particles, and
+    /// applied once ready, which may take several frames. The physics then runs at its own rate,
+    /// independently of rendering, which keeps huge simulations interactive.
+    ///
+    /// The forces are integrated over the time elapsed while they were being computed. Particles
+    /// despawned in the meantime are skipped, and particles spawned in the meantime only receive
+    /// forces from the next snapshot on.
+    ///
+    /// [`AsyncComputeTaskPool`]: bevy::tasks::AsyncComputeTaskPool
+    Background,
+    /// Like [`ForceComputation::Immediate`], but the particles are split between the threads of
+    /// the [`ComputeTaskPool`].
+    ///
+    /// The results are deterministic, bit for bit, whatever the number of threads: each particle
+    /// sums the forces it receives itself, from its neighbors in a fixed order. Determinism costs
+    /// twice the force evaluations of [`ForceComputation::Immediate`], which evaluates each pair
+    /// once for both particles, so this only pays off with enough threads.
+    Parallel,
+}
+
+impl ForceComputation {
+    /// Whether forces are computed and applied within the frame.
+    fn is_immediate(self) -> bool {
+        matches!(self, Self::Immediate | Self::Parallel)
+    }
+}
+
+/// Caps the work of computing the forces each frame, to keep large simulations interactive on
+/// weak hardware, with [`ForceComputation::Immediate`] or [`ForceComputation::Parallel`].
+///
+/// With `max_pairs_per_frame`, only the accelerations of some of the particles are computed each
+/// frame, going round-robin over the particles and stopping once about that many neighbors were
+/// visited, at least one particle being updated every frame. The other particles keep the
+/// acceleration they were last given, so the forces lag behind the positions by up to the number
+/// of frames a full round takes: particles overshoot their equilibrium distances, and fast ones
+/// can pass through each other. Particles spawned since their turn feel no force until it comes.
+///
+/// The particles of a round are computed on the current thread, even with
+/// [`ForceComputation::Parallel`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub struct ComputeBudget {
+    pub max_pairs_per_frame: Option<usize>,
+}
+
+/// Keeps the particles from drifting away as a whole.
+///
+/// Asymmetric attractions don't conserve momentum, so the whole cloud of particles slowly drifts
+/// across the world. When enabled, the mean velocity of the particles is subtracted from every
+/// particle each frame, so that their center of mass stays where it started. This is a viewing
+/// convenience rather than physics: it changes the absolute positions of the particles, but not
+/// their positions relative to each other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub struct RecenterConfig {
+    pub enabled: bool,
+}
+
+/// Stretches the interactions along each axis, so that particles feel each other `x` times as far
+/// horizontally and `y` times as far vertically, which produces streaky, layered structures.
+///
+/// This changes the metric the forces are computed with: the distance between two particles is the
+/// length of their difference with its components divided by `x` and `y` respectively, and is
+/// compared to `rmin` and `rmax` as usual. Forces still act along the actual direction between the
+/// particles. The default of `1` along both axes leaves the usual Euclidean distance.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct Anisotropy {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Default for Anisotropy {
+    fn default() -> Self {
+        Self { x: 1.0, y: 1.0 }
+    }
+}
+
+impl Anisotropy {
+    /// The distance the forces are computed with for two particles `difference` apart.
+    pub fn distance(&self, difference: RealVec2) -> Real {
+        (difference / RealVec2::new(real(self.x), real(self.y))).length()
+    }
+
+    /// How much farther particles interact along the axis they interact the farthest on.
+    fn max_stretch(&self) -> f32 {
+        self.x.max(self.y)
+    }
+}
+
+/// A soft spring pulling the particles that stray farther than `radius` from the center of the
+/// world back toward it, so that they gather in a blob floating in open space rather than filling
+/// the world.
+///
+/// Particles within `radius` of the center feel nothing, and the pull beyond it grows linearly
+/// with the distance past `radius`, by `strength` per unit of distance. Distances are measured on
+/// the torus, so `radius` should stay well below half the size of the world.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct Containment {
+    pub radius: f32,
+    pub strength: f32,
+    pub enabled: bool,
+}
+
+impl Default for Containment {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            strength: 1.0,
+            enabled: false,
+        }
+    }
+}
+
+impl Containment {
+    /// The acceleration of a particle at `offset` from the center of the world.
+    pub fn acceleration(&self, offset: RealVec2) -> RealVec2 {
+        let distance = offset.length();
+        let radius = real(self.radius);
+        if !self.enabled || distance <= radius {
+            return RealVec2::ZERO;
+        }
+        -real(self.strength) * (distance - radius) * offset / distance
+    }
+}
+
+/// Makes the attraction of each particle depend on how crowded it is, for self-limiting clusters.
+///
+/// A particle feeling more than `threshold` particles, that is with more than `threshold`
+/// particles within its `rmax`, has its attraction to the others multiplied by `above`, and by
+/// `below` otherwise. A negative multiplier turns the attraction into repulsion. The repulsion
+/// below `rmin` is never multiplied, so particles still can't overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct QuorumSensing {
+    pub threshold: usize,
+    pub below: f32,
+    pub above: f32,
+}
+
+impl QuorumSensing {
+    /// The multiplier of the attraction of a particle feeling `neighbors` particles.
+    pub fn multiplier(&self, neighbors: usize) -> f32 {
+        if neighbors > self.threshold {
+            self.above
+        } else {
+            self.below
+        }
+    }
+}
+
 /// Particles with the `i`th color are attracted by particles with the `j`th color by
 /// `self.0[i][j]`.
-#[derive(Debug, Clone, Resource, Default)]
+#[derive(Debug, Clone, Resource, Default, PartialEq)]
 pub struct ColorAttractions(pub Vec<Vec<Attraction>>);
 
-fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera2dBundle {
-        projection: OrthographicProjection {
-            left: -1.0,
-            right: 1.0,
-            bottom: -1.0,
-            top: 1.0,
-            scaling_mode: ScalingMode::None,
-            ..Default::default()
-        },
-        ..Default::default()
-    });
+/// [`ColorAttractions`] for a fixed number `N` of colors, so that the matrix is always square and
+/// its dimensions are checked at compile time.
+#[derive(Debug, Clone, Copy)]
+pub struct ColorAttractionsN<const N: usize>(pub [[Attraction; N]; N]);
+
+impl ColorAttractions {
+    /// Returns the pairs of colors `(i, j)`, with `i < j`, such that the attraction of `i` by `j`
+    /// and of `j` by `i` differ by more than `tolerance`.
+    ///
+    /// Such pairs don't conserve momentum, which makes the particles drift as a whole, see
+    /// [`RecenterConfig`]. Entries missing from a non-square matrix are ignored.
+    pub fn asymmetric_pairs(&self, tolerance: f32) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (i, row) in self.0.iter().enumerate() {
+            for (j, attraction) in row.iter().enumerate().skip(i + 1) {
+                let Some(opposite) = self.0.get(j).and_then(|row| row.get(i)) else {
+                    continue;
+                };
+                if (attraction.0 - opposite.0).abs() > tolerance {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+}
+
+impl<const N: usize> From<ColorAttractionsN<N>> for ColorAttractions {
+    fn from(attractions: ColorAttractionsN<N>) -> Self {
+        Self(attractions.0.iter().map(|row| row.to_vec()).collect())
+    }
 }
 
 #[derive(Debug, Clone, Default, Resource)]
@@ -105,127 +892,678 @@ fn setup_color_materials(
 #[derive(Debug, Clone, Default, Resource)]
 struct MeshHandle(Mesh2dHandle);
 
-fn setup_mesh(mut meshes: ResMut<Assets<Mesh>>, mut handle: ResMut<MeshHandle>) {
-    let circle = Circle {
-        radius: 1.0,
-        vertices: 8,
+/// The mesh of every particle, drawn scaled down so that a radius of `1` is a particle radius.
+#[derive(Debug, Clone, Default)]
+pub enum ParticleMesh {
+    /// An octagon approximating a circle.
+    #[default]
+    Circle,
+    /// A mesh already added to the [`Assets<Mesh>`].
+    Handle(Handle<Mesh>),
+    /// A mesh to add to the [`Assets<Mesh>`].
+    Mesh(Mesh),
+}
+
+fn setup_mesh(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut handle: ResMut<MeshHandle>,
+    particle_mesh: ParticleMesh,
+) {
+    let mesh = match particle_mesh {
+        ParticleMesh::Circle => meshes.add(
+            Circle {
+                radius: 1.0,
+                vertices: 8,
+            }
+            .into(),
+        ),
+        ParticleMesh::Handle(mesh) => mesh,
+        ParticleMesh::Mesh(mesh) => meshes.add(mesh),
     };
-    handle.0 = meshes.add(circle.into()).into();
+    handle.0 = mesh.into();
 }
 
-fn setup_mesh_and_color(
+/// Makes particles without a mesh visible, whether they were spawned initially or at runtime.
+fn insert_mesh_and_color(
     mut commands: Commands,
     mesh: Res<MeshHandle>,
     materials: Res<ColorHandles>,
-    query: Query<(&ColorId, Entity)>,
+    query: Query<(&ColorId, &Position, Entity), Without<Mesh2dHandle>>,
 ) {
-    for (&color, entity) in query.iter() {
+    for (&color, position, entity) in query.iter() {
         commands.entity(entity).insert(ColorMesh2dBundle {
             mesh: mesh.0.clone(),
             material: materials.0[color.0].clone(),
+            transform: particle_transform(position),
             ..Default::default()
         });
     }
 }
 
-fn update_position(time: Res<Time>, mut query: Query<(&mut Position, &Velocity)>) {
-    let delta = time.delta_seconds();
-    for (mut position, velocity) in &mut query {
-        position.0 += delta * velocity.0;
-        position.0.x = position.0.x - 2.0 * f32::round(position.0.x / 2.0);
-        position.0.y = position.0.y - 2.0 * f32::round(position.0.y / 2.0);
+/// The resources choosing the time step of the simulation.
+#[derive(SystemParam)]
+struct TimeStepSettings<'w, 's> {
+    time: Res<'w, Time>,
+    max_delta: Res<'w, MaxDelta>,
+    fixed_time_step: Option<Res<'w, FixedTimeStep>>,
+    adaptive_timestep: Option<Res<'w, AdaptiveTimestep>>,
+    velocities: Query<'w, 's, &'static Velocity>,
+}
+
+impl TimeStepSettings<'_, '_> {
+    /// The time step of a frame while the simulation runs.
+    fn running_delta(&self) -> f32 {
+        match &self.adaptive_timestep {
+            Some(adaptive_timestep) => {
+                let max_speed = self
+                    .velocities
+                    .iter()
+                    .map(|velocity| single(velocity.0.length()))
+                    .fold(0.0, f32::max);
+                adaptive_timestep.delta(max_speed)
+            }
+            None if self.fixed_time_step.is_some() => self.max_delta.0,
+            None => self.time.delta_seconds().min(self.max_delta.0),
+        }
+    }
+}
+
+fn update_simulation_time(
+    settings: TimeStepSettings,
+    paused: Res<Paused>,
+    mut steps: EventReader<StepSimulation>,
+    mut simulation_time: ResMut<SimulationTime>,
+) {
+    // Several steps requested in the same frame only advance by one step
+    let stepped = steps.iter().count() > 0;
+    simulation_time.delta = match (paused.0, stepped) {
+        (true, false) => 0.0,
+        (true, true) => settings.max_delta.0,
+        (false, _) => settings.running_delta(),
+    };
+    simulation_time.elapsed += simulation_time.delta;
+}
+
+fn insert_acceleration(
+    mut commands: Commands,
+    query: Query<Entity, (With<Velocity>, Without<Acceleration>)>,
+) {
+    for entity in &query {
+        commands.entity(entity).insert(Acceleration::default());
+    }
+}
+
+/// The first half of the velocity Verlet step: a half kick with the acceleration of the previous
+/// step, then a drift, the second half kick being given by [`update_velocity`]. Forces computed
+/// in the background are applied in one go instead, so only the drift applies to them.
+fn update_position(
+    simulation_time: Res<SimulationTime>,
+    reverse_time: Res<ReverseTime>,
+    force_computation: Res<ForceComputation>,
+    bounds: Res<WorldBounds>,
+    mut query: Query<(&mut Position, &mut Velocity, Option<&Acceleration>)>,
+) {
+    let delta = reverse_time.direction() * real(simulation_time.delta);
+    let immediate = force_computation.is_immediate();
+    for (mut position, mut velocity, acceleration) in &mut query {
+        if let (true, Some(acceleration)) = (immediate, acceleration) {
+            velocity.0 += delta / 2.0 * acceleration.0;
+        }
+        (position.0, velocity.0) = bounds.wrap_motion(position.0 + delta * velocity.0, velocity.0);
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn update_velocity(
-    time: Res<Time>,
-    attraction_radius: Res<AttractionRadius>,
-    color_attractions: Res<ColorAttractions>,
-    mut query: Query<(&mut Velocity, &Position, &ColorId, Entity)>,
+    simulation_time: Res<SimulationTime>,
+    reverse_time: Res<ReverseTime>,
+    force_computation: Res<ForceComputation>,
+    compute_budget: Res<ComputeBudget>,
+    force_settings: ForceSettings,
+    diagnostics: Option<ResMut<Diagnostics>>,
+    mut grid: Local<NeighborGrid>,
+    // The first particle of the next round with a `ComputeBudget`
+    mut next_particle: Local<usize>,
+    mut query: Query<(
+        &mut Velocity,
+        Option<&mut Acceleration>,
+        &Position,
+        &ColorId,
+        &SimulationId,
+    )>,
 ) {
-    let delta = time.delta_seconds();
-    let AttractionRadius { rmin, rmax } = *attraction_radius;
-    let color_attractions = &*color_attractions;
+    if !force_computation.is_immediate() {
+        return;
+    }
+    let timer = PhysicsTimer::start();
+    let delta = reverse_time.direction() * real(simulation_time.delta);
+    let force_models = force_settings.force_models();
 
-    let mut particle_pairs = query.iter_combinations_mut();
-    while let Some([query_a, query_b]) = particle_pairs.fetch_next() {
-        let (mut velocity_a, position_a, &color_a, entity_a) = query_a;
-        let (mut velocity_b, position_b, &color_b, entity_b) = query_b;
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    let mut simulations = Vec::new();
+    for (_, _, &position, &color, &simulation) in &query {
+        positions.push(position);
+        colors.push(color);
+        simulations.push(simulation);
+    }
+    // `None` for the particles that keep their acceleration from a previous frame
+    let accelerations: Vec<_> = match compute_budget.max_pairs_per_frame {
+        Some(max_pairs) if !positions.is_empty() => {
+            let start = *next_particle % positions.len();
+            let updated = force_models.budgeted_accelerations(
+                &positions,
+                &colors,
+                &simulations,
+                &mut grid,
+                start,
+                max_pairs,
+            );
+            *next_particle = (start + updated.len()) % positions.len();
+
+            let mut accelerations = vec![None; positions.len()];
+            for (index, acceleration) in updated {
+                accelerations[index] = Some(acceleration);
+            }
+            accelerations
+        }
+        _ => force_models
+            .accelerations(&positions, &colors, &simulations, &mut grid)
+            .into_iter()
+            .map(Some)
+            .collect(),
+    };
 
-        // Don't attract/repell an entity from itself
-        if entity_a == entity_b {
-            continue;
+    // The query iterates in the same order as above since no entity was added or removed since.
+    for ((mut velocity, stored, ..), acceleration) in query.iter_mut().zip(accelerations) {
+        // Particles spawned this frame get their acceleration stored from the next frame on
+        match (stored, acceleration) {
+            (Some(mut stored), Some(acceleration)) => {
+                velocity.0 += delta / 2.0 * acceleration;
+                stored.0 = acceleration;
+            }
+            (Some(stored), None) => velocity.0 += delta / 2.0 * stored.0,
+            (None, Some(acceleration)) => velocity.0 += delta * acceleration,
+            (None, None) => {}
         }
+    }
+    timer.stop(diagnostics);
+}
 
-        let distance = toroidal_distance(position_a, position_b).max(0.01);
-        let (attraction_a_by_b, attraction_b_by_a) =
-            attraction_factor(distance, color_a, color_b, color_attractions, rmin, rmax);
+fn recenter(recenter: Res<RecenterConfig>, mut query: Query<(&mut Velocity, &SimulationId)>) {
+    if !recenter.enabled {
+        return;
+    }
+
+    // Each simulation is recentered separately
+    let mut sums = HashMap::<SimulationId, (RealVec2, usize)>::default();
+    for (velocity, &simulation) in &query {
+        let (sum, count) = sums.entry(simulation).or_insert((RealVec2::ZERO, 0));
+        *sum += velocity.0;
+        *count += 1;
+    }
+    for (mut velocity, simulation) in &mut query {
+        let (sum, count) = sums[simulation];
+        velocity.0 -= sum / count as Real;
+    }
+}
 
-        let a_to_b_direction = toroidal_difference(position_a, position_b)
-            .try_normalize()
-            .unwrap_or(Vec2 { x: 1.0, y: 0.0 });
+/// The resources parameterizing the forces between particles.
+#[derive(SystemParam)]
+struct ForceSettings<'w, 's> {
+    attraction_radius: Res<'w, AttractionRadius>,
+    color_radius_scale: Res<'w, ColorRadiusScale>,
+    color_attractions: Res<'w, ColorAttractions>,
+    peak_fraction: Res<'w, PeakFraction>,
+    kernel: Res<'w, Kernel>,
+    smooth_cutoff: Res<'w, SmoothCutoff>,
+    anisotropy: Res<'w, Anisotropy>,
+    containment: Res<'w, Containment>,
+    force_computation: Res<'w, ForceComputation>,
+    quorum_sensing: Option<Res<'w, QuorumSensing>>,
+    simulation_overrides: Res<'w, SimulationOverrides>,
+    bounds: Res<'w, WorldBounds>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
 
-        velocity_a.0 += delta * attraction_a_by_b.0 * a_to_b_direction;
-        velocity_b.0 -= delta * attraction_b_by_a.0 * a_to_b_direction;
+impl ForceSettings<'_, '_> {
+    fn force_models(&self) -> ForceModels {
+        let main = ForceModel {
+            attraction_radius: *self.attraction_radius,
+            color_radius_scale: self.color_radius_scale.clone(),
+            color_attractions: self.color_attractions.clone(),
+            peak_fraction: self.peak_fraction.0,
+            kernel: *self.kernel,
+            smooth_cutoff: *self.smooth_cutoff,
+        };
+        let overrides = self
+            .simulation_overrides
+            .0
+            .iter()
+            .map(|(&simulation, settings)| {
+                let model = ForceModel {
+                    attraction_radius: settings.attraction_radius.unwrap_or(main.attraction_radius),
+                    color_attractions: settings
+                        .color_attractions
+                        .clone()
+                        .unwrap_or_else(|| main.color_attractions.clone()),
+                    ..main.clone()
+                };
+                (simulation, model)
+            })
+            .collect();
+        ForceModels {
+            main,
+            overrides,
+            anisotropy: *self.anisotropy,
+            containment: *self.containment,
+            quorum_sensing: self.quorum_sensing.as_deref().copied(),
+            parallel: *self.force_computation == ForceComputation::Parallel,
+            bounds: *self.bounds,
+        }
     }
 }
 
-fn toroidal_distance(position_a: &Position, position_b: &Position) -> f32 {
-    let diff = toroidal_difference(position_a, position_b);
-    diff.length()
+/// How many particles each task computes the acceleration of with
+/// [`ForceComputation::Parallel`].
+const PARALLEL_CHUNK_SIZE: usize = 256;
+
+/// The [`ForceModel`] of every simulation.
+#[derive(Debug, Clone)]
+struct ForceModels {
+    main: ForceModel,
+    overrides: HashMap<SimulationId, ForceModel>,
+    anisotropy: Anisotropy,
+    containment: Containment,
+    quorum_sensing: Option<QuorumSensing>,
+    /// Whether to use [`Self::parallel_accelerations`].
+    parallel: bool,
+    bounds: WorldBounds,
 }
 
-/// A to B
-fn toroidal_difference(base: &Position, tip: &Position) -> Vec2 {
-    let mut dir = tip.0 - base.0;
-    if dir.x.abs() > 1.0 {
-        dir.x = dir.x - 2.0;
+impl ForceModels {
+    fn get(&self, simulation: SimulationId) -> &ForceModel {
+        self.overrides.get(&simulation).unwrap_or(&self.main)
+    }
+
+    /// Returns the acceleration of each particle caused by all the others of the same
+    /// simulation.
+    fn accelerations(
+        &self,
+        positions: &[Position],
+        colors: &[ColorId],
+        simulations: &[SimulationId],
+        grid: &mut NeighborGrid,
+    ) -> Vec<RealVec2> {
+        let _span = info_span!("accelerations", particles = positions.len()).entered();
+        self.rebuild_grid(positions, grid);
+
+        let multipliers = match self.quorum_sensing {
+            Some(quorum_sensing) => self
+                .neighbor_counts(positions, colors, simulations, grid)
+                .into_iter()
+                .map(|neighbors| real(quorum_sensing.multiplier(neighbors)))
+                .collect(),
+            None => vec![1.0; positions.len()],
+        };
+
+        let mut accelerations = if self.parallel {
+            self.parallel_accelerations(positions, colors, simulations, &multipliers, grid)
+        } else {
+            self.pair_accelerations(positions, colors, simulations, &multipliers, grid)
+        };
+
+        if self.containment.enabled {
+            for (acceleration, position) in accelerations.iter_mut().zip(positions) {
+                *acceleration += self.containment_acceleration(position);
+            }
+        }
+        accelerations
+    }
+
+    /// Like [`Self::accelerations`], but only for the particles from `start` on, wrapping around,
+    /// until about `max_pairs` neighbors were visited, see [`ComputeBudget`]. Returns the index
+    /// and acceleration of each particle computed, in order.
+    fn budgeted_accelerations(
+        &self,
+        positions: &[Position],
+        colors: &[ColorId],
+        simulations: &[SimulationId],
+        grid: &mut NeighborGrid,
+        start: usize,
+        max_pairs: usize,
+    ) -> Vec<(usize, RealVec2)> {
+        let _span = info_span!("budgeted_accelerations", particles = positions.len()).entered();
+        self.rebuild_grid(positions, grid);
+
+        let mut pairs = 0;
+        let mut updated = Vec::new();
+        for a in (start..positions.len()).chain(0..start) {
+            if pairs >= max_pairs && !updated.is_empty() {
+                break;
+            }
+
+            let (acceleration, visited) =
+                self.full_acceleration(a, positions, colors, simulations, grid);
+            pairs += visited;
+            updated.push((a, acceleration));
+        }
+        updated
+    }
+
+    /// Computes the acceleration of the particle `a` on its own, like [`Self::accelerations`]
+    /// does for all particles, with its quorum multiplier and containment, using the grid already
+    /// built. Also returns how many particles were visited around it.
+    fn full_acceleration(
+        &self,
+        a: usize,
+        positions: &[Position],
+        colors: &[ColorId],
+        simulations: &[SimulationId],
+        grid: &NeighborGrid,
+    ) -> (RealVec2, usize) {
+        let mut pairs = 0;
+        let multiplier = match self.quorum_sensing {
+            Some(quorum_sensing) => {
+                let (neighbors, visited) =
+                    self.neighbor_count(a, positions, colors, simulations, grid);
+                pairs += visited;
+                real(quorum_sensing.multiplier(neighbors))
+            }
+            None => 1.0,
+        };
+        let (mut acceleration, visited) =
+            self.acceleration(a, positions, colors, simulations, multiplier, grid);
+        pairs += visited;
+        if self.containment.enabled {
+            acceleration += self.containment_acceleration(&positions[a]);
+        }
+        (acceleration, pairs)
+    }
+
+    /// Rebuilds `grid` with cells large enough for the largest radius of any simulation.
+    fn rebuild_grid(&self, positions: &[Position], grid: &mut NeighborGrid) {
+        let max_rmax = self
+            .overrides
+            .values()
+            .map(|model| model.attraction_radius.rmax * model.color_radius_scale.max())
+            .fold(
+                self.main.attraction_radius.rmax * self.main.color_radius_scale.max(),
+                f32::max,
+            );
+        grid.rebuild(
+            positions,
+            max_rmax * self.anisotropy.max_stretch(),
+            &self.bounds,
+        );
+    }
+
+    fn containment_acceleration(&self, position: &Position) -> RealVec2 {
+        let center = real_vec2(self.bounds.center());
+        let offset = toroidal_delta(center, position.0, &self.bounds);
+        self.containment.acceleration(offset)
+    }
+
+    /// Visits each pair of nearby particles once, updating both particles.
+    fn pair_accelerations(
+        &self,
+        positions: &[Position],
+        colors: &[ColorId],
+        simulations: &[SimulationId],
+        multipliers: &[Real],
+        grid: &NeighborGrid,
+    ) -> Vec<RealVec2> {
+        let mut accelerations = vec![RealVec2::ZERO; positions.len()];
+        grid.for_each_pair(|a, b| {
+            if simulations[a] != simulations[b] {
+                return;
+            }
+            let force_model = self.get(simulations[a]);
+
+            let difference = toroidal_delta(positions[a].0, positions[b].0, &self.bounds);
+            let distance = self.anisotropy.distance(difference).max(0.01);
+            let (attraction_a_by_b, attraction_b_by_a) = force_model.scaled_attraction_factor(
+                distance,
+                colors[a],
+                colors[b],
+                (multipliers[a], multipliers[b]),
+            );
+
+            let a_to_b_direction = difference.try_normalize().unwrap_or(RealVec2::X);
+            // Across the twisted edges of a Klein bottle, each particle sees the other mirrored
+            let b_to_a_direction = if self.bounds.topology == Topology::Klein {
+                toroidal_delta(positions[b].0, positions[a].0, &self.bounds)
+                    .try_normalize()
+                    .unwrap_or(-RealVec2::X)
+            } else {
+                -a_to_b_direction
+            };
+
+            accelerations[a] += pair_acceleration(attraction_a_by_b, a_to_b_direction);
+            accelerations[b] += pair_acceleration(attraction_b_by_a, b_to_a_direction);
+        });
+        accelerations
+    }
+
+    /// Computes the acceleration of each particle on its own, from its neighbors in a fixed
+    /// order, spread over the [`ComputeTaskPool`]. Every pair is visited twice, once for each
+    /// particle, but the result doesn't depend on how the particles are split between threads.
+    fn parallel_accelerations(
+        &self,
+        positions: &[Position],
+        colors: &[ColorId],
+        simulations: &[SimulationId],
+        multipliers: &[Real],
+        grid: &NeighborGrid,
+    ) -> Vec<RealVec2> {
+        let indices: Vec<usize> = (0..positions.len()).collect();
+        let chunks = indices.par_chunk_map(ComputeTaskPool::get(), PARALLEL_CHUNK_SIZE, |chunk| {
+            chunk
+                .iter()
+                .map(|&a| {
+                    self.acceleration(a, positions, colors, simulations, multipliers[a], grid)
+                        .0
+                })
+                .collect::<Vec<_>>()
+        });
+        chunks.into_iter().flatten().collect()
+    }
+
+    /// Computes the acceleration of the particle `a` on its own, with its attractions multiplied
+    /// by `multiplier`. Also returns how many particles were visited around it.
+    fn acceleration(
+        &self,
+        a: usize,
+        positions: &[Position],
+        colors: &[ColorId],
+        simulations: &[SimulationId],
+        multiplier: Real,
+        grid: &NeighborGrid,
+    ) -> (RealVec2, usize) {
+        let force_model = self.get(simulations[a]);
+        let mut acceleration = RealVec2::ZERO;
+        let mut visited = 0;
+        // The grid lists the particles of the cells around `a` in a fixed order
+        grid.for_each_near(positions[a].0, grid.min_cell_size(), |b| {
+            visited += 1;
+            if a == b || simulations[a] != simulations[b] {
+                return;
+            }
+            let difference = toroidal_delta(positions[a].0, positions[b].0, &self.bounds);
+            let distance = self.anisotropy.distance(difference).max(0.01);
+            let attraction_a_by_b =
+                force_model.attraction(distance, colors[a], colors[b], multiplier);
+            let a_to_b_direction = difference.try_normalize().unwrap_or(RealVec2::X);
+            acceleration += pair_acceleration(attraction_a_by_b, a_to_b_direction);
+        });
+        (acceleration, visited)
     }
-    if dir.y.abs() > 1.0 {
-        dir.y = dir.y - 2.0;
+
+    /// Returns how many particles of the same simulation the particle `a` feels, like
+    /// [`Self::neighbor_counts`] does for all particles, and how many particles were visited
+    /// around it.
+    fn neighbor_count(
+        &self,
+        a: usize,
+        positions: &[Position],
+        colors: &[ColorId],
+        simulations: &[SimulationId],
+        grid: &NeighborGrid,
+    ) -> (usize, usize) {
+        let force_model = self.get(simulations[a]);
+        let (mut count, mut visited) = (0, 0);
+        grid.for_each_near(positions[a].0, grid.min_cell_size(), |b| {
+            visited += 1;
+            if a == b || simulations[a] != simulations[b] {
+                return;
+            }
+            let difference = toroidal_delta(positions[a].0, positions[b].0, &self.bounds);
+            if self.anisotropy.distance(difference) <= force_model.rmax(colors[a]) {
+                count += 1;
+            }
+        });
+        (count, visited)
+    }
+
+    /// Returns how many particles of the same simulation each particle feels, that is how many
+    /// are within its `rmax`, using the grid already built by [`Self::accelerations`].
+    fn neighbor_counts(
+        &self,
+        positions: &[Position],
+        colors: &[ColorId],
+        simulations: &[SimulationId],
+        grid: &NeighborGrid,
+    ) -> Vec<usize> {
+        let mut counts = vec![0; positions.len()];
+        grid.for_each_pair(|a, b| {
+            if simulations[a] != simulations[b] {
+                return;
+            }
+            let force_model = self.get(simulations[a]);
+
+            let difference = toroidal_delta(positions[a].0, positions[b].0, &self.bounds);
+            let distance = self.anisotropy.distance(difference);
+            if distance <= force_model.rmax(colors[a]) {
+                counts[a] += 1;
+            }
+            if distance <= force_model.rmax(colors[b]) {
+                counts[b] += 1;
+            }
+        });
+        counts
     }
-    dir
 }
 
-/// Calculates how much a particle A is attracted to a particle B. Negative values represent
-/// equivalent repulsion.
-///
-/// Given the distance `d` between the two particles, this attraction factor `F` is calculated as
-/// follows:
-///
-/// - If `d <= rmin`, `F < 0` to make the particles repell. `F = d / rmin - 1`: at `d = 0`, the
-/// particles repell with a force of `1` and at `d = rmin`, their velocity stays fixed.
-///
-/// - If `rmin <= d <= rmax`, the attraction factor is calculated using the appropriate entry in
-/// `color_attractions`: `F = 0` at `d = rmin` at `d = rmax`, and peaks halfway.
-///
-/// - If `d > rmax`, `F = 0`.
+/// Everything needed to compute the forces between particles, detached from the ECS so that it
+/// can be sent to another thread.
+#[derive(Debug, Clone)]
+struct ForceModel {
+    attraction_radius: AttractionRadius,
+    color_radius_scale: ColorRadiusScale,
+    color_attractions: ColorAttractions,
+    peak_fraction: f32,
+    kernel: Kernel,
+    smooth_cutoff: SmoothCutoff,
+}
+
+/// The acceleration of a particle feeling another particle in the unit direction `toward_other`
+/// with `attraction`, as given by [`ForceModel::attraction`].
 ///
-/// The first return value indicates how particle A is attracted by particle B, the second the
-/// opposite.
-fn attraction_factor(
-    distance: f32,
-    color_a: ColorId,
-    color_b: ColorId,
-    color_attractions: &ColorAttractions,
-    rmin: f32,
-    rmax: f32,
-) -> (Attraction, Attraction) {
-    if distance <= rmin {
-        let attraction_factor = distance / rmin - 1.0;
-        (Attraction(attraction_factor), Attraction(attraction_factor))
-    } else if distance <= rmax {
-        let peak_attraction_a_by_b = color_attractions.0[color_a.0][color_b.0];
-        let peak_attraction_b_by_a = color_attractions.0[color_b.0][color_a.0];
-
-        let peak_distance = (rmin + rmax) / 2.0;
-        let distance_scalar = (distance - peak_distance).abs();
+/// This is the sign convention of every force between two particles: a positive attraction pulls
+/// the particle toward the other one and a negative attraction pushes it away. Each particle of a
+/// pair gets its own acceleration, from the direction toward the other particle, which is only
+/// the opposite of the direction the other particle sees away from the twisted edges of a Klein
+/// bottle.
+fn pair_acceleration(attraction: Real, toward_other: RealVec2) -> RealVec2 {
+    debug_assert!(
+        (toward_other.length() - 1.0).abs() < 1e-3,
+        "the direction toward the other particle must be a unit vector"
+    );
+    attraction * toward_other
+}
+
+impl ForceModel {
+    /// Calculates how much a particle A is attracted to a particle B and conversely. The first
+    /// return value indicates how particle A is attracted by particle B, the second the opposite.
+    ///
+    /// The two may differ in magnitude with asymmetric `color_attractions`, and in shape with
+    /// `color_radius_scale`, since each particle feels the other with its own radii.
+    fn attraction_factor(
+        &self,
+        distance: Real,
+        color_a: ColorId,
+        color_b: ColorId,
+    ) -> (Real, Real) {
+        self.scaled_attraction_factor(distance, color_a, color_b, (1.0, 1.0))
+    }
+
+    /// [`Self::attraction_factor`] with the attractions of A and B, but not their repulsion,
+    /// multiplied by the respective `multipliers`.
+    fn scaled_attraction_factor(
+        &self,
+        distance: Real,
+        color_a: ColorId,
+        color_b: ColorId,
+        (multiplier_a, multiplier_b): (Real, Real),
+    ) -> (Real, Real) {
         (
-            Attraction(distance_scalar * peak_attraction_a_by_b.0),
-            Attraction(distance_scalar * peak_attraction_b_by_a.0),
+            self.attraction(distance, color_a, color_b, multiplier_a),
+            self.attraction(distance, color_b, color_a, multiplier_b),
         )
-    } else {
-        (Attraction(0.0), Attraction(0.0))
+    }
+
+    /// The largest distance a particle with the color `color` feels other particles from.
+    fn rmax(&self, color: ColorId) -> Real {
+        real(self.color_radius_scale.get(color) * self.attraction_radius.rmax)
+    }
+
+    /// Calculates how much a particle with the color `receiver` is attracted to a particle with
+    /// the color `source`, the attraction being multiplied by `multiplier`. Negative values
+    /// represent equivalent repulsion.
+    ///
+    /// Given the distance `d` between the two particles, and `rmin` and `rmax` scaled by the
+    /// `color_radius_scale` of `receiver`, this attraction factor `F` is calculated as follows:
+    ///
+    /// - If `d <= rmin`, `F < 0` to make the particles repell. `F = d / rmin - 1`: at `d = 0`,
+    ///   the particles repell with a force of `1` and at `d = rmin`, their velocity stays fixed.
+    ///
+    /// - If `rmin <= d <= rmax`, `F` rises from `0` at `d = rmin` to the appropriate entry in
+    ///   `color_attractions` at the peak distance `rmin + peak_fraction * (rmax - rmin)`, then
+    ///   falls back to `0` at `d = rmax`, following the profile given by `kernel`. This is then
+    ///   multiplied by `multiplier` and by the ramp of `smooth_cutoff`.
+    ///
+    /// - If `d > rmax`, `F = 0`.
+    fn attraction(
+        &self,
+        distance: Real,
+        receiver: ColorId,
+        source: ColorId,
+        multiplier: Real,
+    ) -> Real {
+        let scale = real(self.color_radius_scale.get(receiver));
+        let (rmin, rmax) = (
+            scale * real(self.attraction_radius.rmin),
+            scale * real(self.attraction_radius.rmax),
+        );
+        if distance <= rmin {
+            distance / rmin - 1.0
+        } else if distance <= rmax {
+            let peak_attraction = self.color_attractions.0[receiver.0][source.0];
+
+            let peak_fraction = real(self.peak_fraction.clamp(0.0, 1.0));
+            let peak_distance = rmin + peak_fraction * (rmax - rmin);
+            // The divisors are clamped for peaks right at `rmin` or `rmax`
+            let distance_scalar = if distance < peak_distance {
+                (distance - rmin) / (peak_distance - rmin).max(Real::EPSILON)
+            } else {
+                (rmax - distance) / (rmax - peak_distance).max(Real::EPSILON)
+            };
+            let ramp = self.smooth_cutoff.ramp(distance, rmin, rmax);
+            multiplier * ramp * self.kernel.shape(distance_scalar) * real(peak_attraction.0)
+        } else {
+            0.0
+        }
     }
 }
 
@@ -234,8 +1572,7 @@ fn update_transform(
     mut query: Query<(Option<&mut Transform>, &Position, Entity), Changed<Position>>,
 ) {
     for (transform, position, entity) in query.iter_mut() {
-        let new_transform = Transform::from_translation(Vec3::new(position.0.x, position.0.y, 0.0))
-            .with_scale(Vec3::splat(0.01));
+        let new_transform = particle_transform(position);
         if let Some(mut transform) = transform {
             *transform = new_transform;
         } else {
@@ -244,6 +1581,15 @@ fn update_transform(
     }
 }
 
+/// The radius particles are drawn with.
+pub(crate) const PARTICLE_RADIUS: f32 = 0.01;
+
+fn particle_transform(position: &Position) -> Transform {
+    Transform::from_translation(single_vec2(position.0).extend(0.0))
+        .with_scale(Vec3::splat(PARTICLE_RADIUS))
+}
+
+#[allow(clippy::type_complexity)]
 fn update_material(
     mut commands: Commands,
     handles: Res<ColorHandles>,
@@ -258,3 +1604,508 @@ fn update_material(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::clock::{advance, advance_steps, headless_app, test_plugin};
+
+    /// A single-color model with an attraction of `1` between `rmin = 0.1` and `rmax = 0.5`.
+    fn force_model(kernel: Kernel, peak_fraction: f32) -> ForceModel {
+        ForceModel {
+            attraction_radius: AttractionRadius {
+                rmin: 0.1,
+                rmax: 0.5,
+            },
+            color_radius_scale: ColorRadiusScale::default(),
+            color_attractions: ColorAttractions(vec![vec![Attraction(1.0)]]),
+            peak_fraction,
+            kernel,
+            smooth_cutoff: SmoothCutoff::default(),
+        }
+    }
+
+    #[test]
+    fn attraction_peaks_at_peak_fraction() {
+        // The gaussian only falls to about 1% of the peak at `rmin` and `rmax`
+        for (kernel, edge_tolerance) in [
+            (Kernel::Tent, 1e-4),
+            (Kernel::Smoothstep, 1e-4),
+            (Kernel::Gaussian, 0.02),
+        ] {
+            for (peak_fraction, peak_distance) in [(0.25, 0.2), (0.75, 0.4)] {
+                let model = force_model(kernel, peak_fraction);
+                let attraction = |distance: f32| {
+                    model
+                        .attraction_factor(real(distance), ColorId(0), ColorId(0))
+                        .0
+                };
+
+                let samples = (0..=400).map(|i| 0.1 + 0.4 * i as f32 / 400.0);
+                let argmax = samples
+                    .max_by(|&a, &b| attraction(a).total_cmp(&attraction(b)))
+                    .unwrap();
+                assert!(
+                    (argmax - peak_distance).abs() < 1e-3,
+                    "{kernel:?} {peak_fraction}: peak at {argmax}"
+                );
+                assert!((attraction(peak_distance) - 1.0).abs() < 1e-4);
+                assert!(attraction(0.1).abs() < edge_tolerance);
+                assert!(attraction(0.5).abs() < edge_tolerance);
+                assert_eq!(attraction(0.501), 0.0, "{kernel:?} {peak_fraction}");
+            }
+        }
+    }
+
+    #[derive(Component)]
+    struct Marker;
+
+    #[test]
+    fn spawn_particles_with_keeps_user_components() {
+        let mut app = headless_app(test_plugin(Vec::new()));
+        app.add_startup_system(|mut commands: Commands| {
+            let particles = [-0.5, 0.5].map(|x| Particle {
+                position: Position(RealVec2::new(real(x), real(0.0))),
+                velocity: Velocity(RealVec2::new(real(0.0), real(0.5))),
+                color: ColorId(0),
+            });
+            spawn_particles_with(&mut commands, particles, SimulationId::MAIN, |entity| {
+                entity.insert(Marker);
+            });
+        });
+
+        advance_steps(&mut app, 0.01, 5);
+
+        let mut query = app.world.query_filtered::<&Position, With<Marker>>();
+        let positions: Vec<_> = query.iter(&app.world).map(|position| position.0).collect();
+        assert_eq!(positions.len(), 2);
+        for position in positions {
+            assert!(
+                single(position.y) > 0.0,
+                "particle at {position} didn't move"
+            );
+        }
+    }
+
+    /// The positions of the particles by [`ParticleIndex`].
+    fn positions(app: &mut App) -> Vec<(ParticleIndex, Position)> {
+        let mut positions: Vec<_> = app
+            .world
+            .query::<(&ParticleIndex, &Position)>()
+            .iter(&app.world)
+            .map(|(&index, &position)| (index, position))
+            .collect();
+        positions.sort_by_key(|&(index, _)| index);
+        positions
+    }
+
+    #[test]
+    fn runs_number_the_particles_alike() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut random_particle = || Particle {
+            position: Position(RealVec2::new(
+                real(rng.gen_range(-1.0..1.0)),
+                real(rng.gen_range(-1.0..1.0)),
+            )),
+            velocity: Velocity(RealVec2::ZERO),
+            color: ColorId(rng.gen_range(0..2)),
+        };
+        let particles: Vec<_> = (0..20).map(|_| random_particle()).collect();
+        let spawns: Vec<_> = (0..5).map(|_| random_particle()).collect();
+        let run = || {
+            let mut app = headless_app(test_plugin(particles.clone()));
+            advance_steps(&mut app, 0.01, 2);
+            let despawned = app
+                .world
+                .query::<(Entity, &ParticleIndex)>()
+                .iter(&app.world)
+                .find(|&(_, &index)| index == ParticleIndex(3))
+                .map(|(entity, _)| entity)
+                .unwrap();
+            app.world.send_event(DespawnParticle(despawned));
+            for &particle in &spawns {
+                app.world.send_event(SpawnParticle {
+                    particle,
+                    simulation: SimulationId::MAIN,
+                });
+            }
+            advance_steps(&mut app, 0.01, 2);
+
+            let mut particles: Vec<_> = app
+                .world
+                .query::<(&ParticleIndex, &ColorId, &Position)>()
+                .iter(&app.world)
+                .map(|(&index, &color, position)| {
+                    (index, color, position.0.to_array().map(Real::to_bits))
+                })
+                .collect();
+            particles.sort_by_key(|&(index, ..)| index);
+            particles
+        };
+
+        let (first, second) = (run(), run());
+        assert_eq!(first, second);
+        // The initial particles are numbered in order, then the spawned ones after them
+        let indices: Vec<_> = first.iter().map(|&(index, ..)| index.0).collect();
+        let expected: Vec<_> = (0..25).filter(|&index| index != 3).collect();
+        assert_eq!(indices, expected);
+        let colors: Vec<_> = first.iter().map(|&(_, color, _)| color).collect();
+        let expected: Vec<_> = particles
+            .iter()
+            .chain(&spawns)
+            .enumerate()
+            .filter(|&(index, _)| index != 3)
+            .map(|(_, particle)| particle.color)
+            .collect();
+        assert_eq!(colors, expected);
+    }
+
+    #[test]
+    fn parallel_runs_are_bit_identical() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let particles: Vec<_> = (0..1000)
+            .map(|i| Particle {
+                position: Position(RealVec2::new(
+                    real(rng.gen_range(-1.0..1.0)),
+                    real(rng.gen_range(-1.0..1.0)),
+                )),
+                velocity: Velocity(RealVec2::ZERO),
+                color: ColorId(i % 2),
+            })
+            .collect();
+        let run = || {
+            let mut app = headless_app(ParticleLifePlugin {
+                color_attractions: ColorAttractions(vec![
+                    vec![Attraction(1.0), Attraction(-0.5)],
+                    vec![Attraction(0.8), Attraction(0.2)],
+                ]),
+                force_computation: ForceComputation::Parallel,
+                ..test_plugin(particles.clone())
+            });
+            advance_steps(&mut app, 0.01, 20);
+            positions(&mut app)
+        };
+
+        let (first, second) = (run(), run());
+        assert_eq!(first.len(), particles.len());
+        for ((index, a), (_, b)) in first.iter().zip(&second) {
+            assert_eq!(
+                a.0.to_array().map(Real::to_bits),
+                b.0.to_array().map(Real::to_bits),
+                "{index:?}"
+            );
+        }
+        assert_ne!(
+            first
+                .iter()
+                .map(|(_, position)| position.0)
+                .collect::<Vec<_>>(),
+            particles
+                .iter()
+                .map(|particle| particle.position.0)
+                .collect::<Vec<_>>(),
+            "the particles didn't move"
+        );
+    }
+
+    #[test]
+    fn attraction_pulls_across_the_seam() {
+        let particles = [-0.95, 0.95].map(|x| Particle {
+            position: Position(RealVec2::new(real(x), real(0.0))),
+            velocity: Velocity(RealVec2::ZERO),
+            color: ColorId(0),
+        });
+        let mut app = headless_app(ParticleLifePlugin {
+            color_attractions: ColorAttractions(vec![vec![Attraction(1.0); 2]; 2]),
+            ..test_plugin(particles.to_vec())
+        });
+        let bounds = WorldBounds::default();
+        let wrapped_distance = |positions: &[(ParticleIndex, Position)]| {
+            toroidal_dist(positions[0].1 .0, positions[1].1 .0, &bounds)
+        };
+
+        let before = wrapped_distance(&positions(&mut app));
+        advance_steps(&mut app, 0.01, 10);
+        let after = positions(&mut app);
+
+        assert!(
+            wrapped_distance(&after) < before,
+            "the particles moved apart: {after:?}"
+        );
+        for (index, position) in after {
+            assert!(
+                single(position.0.x).abs() > 0.9,
+                "{index:?} went through the middle to {position:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn spawn_bursts_are_spread_over_frames() {
+        let mut app = headless_app(ParticleLifePlugin {
+            // Out of reach of each other on the grid below, so that they don't move
+            attraction_radius: AttractionRadius {
+                rmin: 0.001,
+                rmax: 0.002,
+            },
+            structural_change_limit: StructuralChangeLimit {
+                max_structural_changes_per_frame: Some(1000),
+            },
+            ..test_plugin(Vec::new())
+        });
+        let grid_position = |i: u32| {
+            let (x, y) = ((i % 100) as f32, (i / 100) as f32);
+            Vec2::new(x, y) / 50.0 - 1.0
+        };
+        for i in 0..10_000 {
+            app.world.send_event(SpawnParticle {
+                particle: Particle {
+                    position: Position(real_vec2(grid_position(i))),
+                    velocity: Velocity(RealVec2::ZERO),
+                    color: ColorId(0),
+                },
+                simulation: SimulationId::MAIN,
+            });
+        }
+
+        for frame in 1..=12 {
+            advance(&mut app, 0.01);
+            let count = positions(&mut app).len();
+            assert_eq!(count, (1000 * frame).min(10_000), "frame {frame}");
+        }
+        // Spawned in the order they were sent
+        for (index, position) in positions(&mut app) {
+            assert_eq!(single_vec2(position.0), grid_position(index.0));
+        }
+    }
+
+    #[test]
+    fn adaptive_timestep_shrinks_after_a_burst() {
+        let particle = Particle {
+            position: Position(RealVec2::ZERO),
+            velocity: Velocity(RealVec2::new(real(0.01), real(0.0))),
+            color: ColorId(0),
+        };
+        let adaptive_timestep = AdaptiveTimestep {
+            min_dt: 0.001,
+            max_dt: 0.02,
+            max_displacement: 0.001,
+        };
+        let mut app = headless_app(ParticleLifePlugin {
+            adaptive_timestep: Some(adaptive_timestep),
+            ..test_plugin(vec![particle])
+        });
+        advance(&mut app, 0.01);
+        let calm_delta = app.world.resource::<SimulationTime>().delta;
+        assert_eq!(calm_delta, adaptive_timestep.max_dt);
+
+        for mut velocity in app.world.query::<&mut Velocity>().iter_mut(&mut app.world) {
+            velocity.0 = RealVec2::new(real(0.5), real(0.0));
+        }
+        advance(&mut app, 0.01);
+        let burst_delta = app.world.resource::<SimulationTime>().delta;
+        assert!(
+            (burst_delta - 0.002).abs() < 1e-6,
+            "time step of {burst_delta} during the burst"
+        );
+    }
+
+    #[test]
+    fn recentering_keeps_the_center_of_mass_in_place() {
+        // The first color chases the second one, which flees, so that the pair drifts as a whole
+        let particles = [(-0.1, 0), (0.1, 1)].map(|(x, color)| Particle {
+            position: Position(RealVec2::new(real(x), real(0.0))),
+            velocity: Velocity(RealVec2::ZERO),
+            color: ColorId(color),
+        });
+        let center_drift = |enabled| {
+            let mut app = headless_app(ParticleLifePlugin {
+                color_attractions: ColorAttractions(vec![
+                    vec![Attraction(0.0), Attraction(1.0)],
+                    vec![Attraction(-1.0), Attraction(0.0)],
+                ]),
+                recenter: RecenterConfig { enabled },
+                ..test_plugin(particles.to_vec())
+            });
+            advance_steps(&mut app, 0.01, 50);
+            let positions = positions(&mut app);
+            single_vec2((positions[0].1 .0 + positions[1].1 .0) / real(2.0)).length()
+        };
+
+        let (drift, recentered_drift) = (center_drift(false), center_drift(true));
+        assert!(drift > 0.01, "the pair didn't drift: {drift}");
+        assert!(
+            recentered_drift < drift / 10.0,
+            "the recentered pair drifted by {recentered_drift}"
+        );
+    }
+
+    #[test]
+    fn unit_anisotropy_is_bit_identical_to_the_euclidean_distance() {
+        let unit = Anisotropy { x: 1.0, y: 1.0 };
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..1000 {
+            let difference = RealVec2::new(
+                real(rng.gen_range(-1.0..1.0)),
+                real(rng.gen_range(-1.0..1.0)),
+            );
+            assert_eq!(
+                unit.distance(difference).to_bits(),
+                difference.length().to_bits(),
+                "{difference}"
+            );
+        }
+
+        let particles: Vec<_> = (0..200)
+            .map(|i| Particle {
+                position: Position(RealVec2::new(
+                    real(rng.gen_range(-1.0..1.0)),
+                    real(rng.gen_range(-1.0..1.0)),
+                )),
+                velocity: Velocity(RealVec2::ZERO),
+                color: ColorId(i % 2),
+            })
+            .collect();
+        let run = |anisotropy| {
+            let mut app = headless_app(ParticleLifePlugin {
+                color_attractions: ColorAttractions(vec![
+                    vec![Attraction(1.0), Attraction(-0.5)],
+                    vec![Attraction(0.8), Attraction(0.2)],
+                ]),
+                anisotropy,
+                ..test_plugin(particles.clone())
+            });
+            advance_steps(&mut app, 0.01, 20);
+            positions(&mut app)
+        };
+        let (unit, default) = (run(unit), run(Anisotropy::default()));
+        for ((index, a), (_, b)) in unit.iter().zip(&default) {
+            assert_eq!(
+                a.0.to_array().map(Real::to_bits),
+                b.0.to_array().map(Real::to_bits),
+                "{index:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn containment_only_pulls_particles_outside_its_radius() {
+        let containment = Containment {
+            radius: 0.5,
+            strength: 2.0,
+            enabled: true,
+        };
+        let outside = containment.acceleration(RealVec2::new(real(0.8), real(0.0)));
+        assert!(single(outside.x) < 0.0 && outside.y == 0.0, "{outside}");
+        assert_eq!(
+            containment.acceleration(RealVec2::new(real(0.2), real(0.3))),
+            RealVec2::ZERO
+        );
+
+        let particles = [(0.8, 0.0), (0.0, 0.2)].map(|(x, y)| Particle {
+            position: Position(RealVec2::new(real(x), real(y))),
+            velocity: Velocity(RealVec2::ZERO),
+            color: ColorId(0),
+        });
+        let mut app = headless_app(ParticleLifePlugin {
+            containment,
+            ..test_plugin(particles.to_vec())
+        });
+        advance_steps(&mut app, 0.01, 5);
+
+        let positions = positions(&mut app);
+        assert!(
+            single(positions[0].1 .0.x) < 0.8,
+            "the outer particle wasn't pulled in: {positions:?}"
+        );
+        assert_eq!(positions[1].1, particles[1].position);
+    }
+
+    #[test]
+    fn positive_attraction_pulls_particles_together() {
+        assert_eq!(pair_acceleration(2.0, RealVec2::X), RealVec2::new(2.0, 0.0));
+
+        for (attraction, closer) in [(1.0, true), (-1.0, false)] {
+            let particles = [-0.075, 0.075].map(|x| Particle {
+                position: Position(RealVec2::new(real(x), real(0.0))),
+                velocity: Velocity(RealVec2::ZERO),
+                color: ColorId(0),
+            });
+            let mut app = headless_app(ParticleLifePlugin {
+                color_attractions: ColorAttractions(vec![vec![Attraction(attraction); 2]; 2]),
+                ..test_plugin(particles.to_vec())
+            });
+            advance_steps(&mut app, 0.01, 2);
+
+            let positions = positions(&mut app);
+            let distance = (positions[1].1 .0.x - positions[0].1 .0.x).abs();
+            assert_eq!(
+                distance < real(0.15),
+                closer,
+                "attraction {attraction}: {distance} apart"
+            );
+        }
+    }
+
+    #[test]
+    fn a_long_frame_only_advances_by_max_delta() {
+        let particle = Particle {
+            position: Position(RealVec2::ZERO),
+            velocity: Velocity(RealVec2::X),
+            color: ColorId(0),
+        };
+        let mut app = headless_app(ParticleLifePlugin {
+            max_delta: MaxDelta(0.01),
+            ..test_plugin(vec![particle])
+        });
+        // A hitch of a whole second, long enough to carry the particle across the world
+        advance(&mut app, 1.0);
+
+        assert_eq!(app.world.resource::<SimulationTime>().delta, 0.01);
+        let x = single(positions(&mut app)[0].1 .0.x);
+        assert!((x - 0.01).abs() < 1e-6, "the particle moved to {x}");
+    }
+
+    #[test]
+    fn quorum_sensing_switches_above_the_threshold() {
+        let quorum_sensing = QuorumSensing {
+            threshold: 3,
+            below: 1.0,
+            above: -1.0,
+        };
+        assert_eq!(quorum_sensing.multiplier(3), 1.0);
+        assert_eq!(quorum_sensing.multiplier(4), -1.0);
+
+        // A particle attracted by a column of neighbors to its right, which aren't attracted back
+        for (neighbors, attracted) in [(3, true), (4, false)] {
+            let center = Particle {
+                position: Position(RealVec2::ZERO),
+                velocity: Velocity(RealVec2::ZERO),
+                color: ColorId(0),
+            };
+            let column = (0..neighbors).map(|i| Particle {
+                position: Position(RealVec2::new(real(0.2), real(0.02 * i as f32 - 0.03))),
+                velocity: Velocity(RealVec2::ZERO),
+                color: ColorId(1),
+            });
+            let mut app = headless_app(ParticleLifePlugin {
+                color_attractions: ColorAttractions(vec![
+                    vec![Attraction(0.0), Attraction(1.0)],
+                    vec![Attraction(0.0), Attraction(0.0)],
+                ]),
+                quorum_sensing: Some(quorum_sensing),
+                ..test_plugin(std::iter::once(center).chain(column).collect())
+            });
+            advance_steps(&mut app, 0.01, 2);
+
+            let x = positions(&mut app)[0].1 .0.x;
+            assert_eq!(
+                x > real(0.0),
+                attracted,
+                "{neighbors} neighbors: the particle moved to {x}"
+            );
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use bevy::{prelude::*, utils::HashMap};
+use rand::Rng;
+
+use std::cmp::Ordering;
+
+use crate::{
+    precision::real_vec2, update_position, ColorHandles, ColorId, ParticleColors,
+    ParticleLifeSystem, Position, RealVec2, SimRng, SimulationTime, Velocity, WorldBounds,
+};
+
+/// Number of distinct opacities a fading particle goes through, each with its own material.
+const FADE_LEVELS: u8 = 16;
+
+/// How long a particle has been alive, in seconds.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct Age(pub f32);
+
+/// Gives particles a limited lifespan, for continuously flowing visuals.
+///
+/// Particles fade out during the last `fade_time` seconds of their life, then respawn at a random
+/// position, at rest, with a random color and an age of `0`. A `fade_time` of `0` disables fading.
+///
+/// The initial particles start with random ages between `0` and `max_age`, so that they don't all
+/// respawn at once.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct Lifespan {
+    pub max_age: f32,
+    pub fade_time: f32,
+}
+
+/// Materials for fading particles, by color and fade level, created as needed.
+#[derive(Debug, Clone, Default, Resource)]
+pub(crate) struct FadeMaterials(HashMap<(usize, u8), Handle<ColorMaterial>>);
+
+pub(crate) fn build(app: &mut App, lifespan: Option<Lifespan>) {
+    if let Some(lifespan) = lifespan {
+        app.insert_resource(lifespan)
+            .add_startup_system(randomize_initial_ages);
+    }
+
+    // Respawns happen before the step, in a fixed order with it for replays to be exact
+    app.add_system(
+        age_particles
+            .before(update_position)
+            .before(ParticleLifeSystem::UpdateMaterial),
+    );
+}
+
+/// Fades the particles out at the end of their life, when they are drawn.
+pub(crate) fn build_fading(app: &mut App) {
+    app.init_resource::<FadeMaterials>()
+        .add_system(fade_particles.after(ParticleLifeSystem::UpdateMaterial));
+}
+
+fn randomize_initial_ages(
+    lifespan: Res<Lifespan>,
+    mut rng: ResMut<SimRng>,
+    mut query: Query<&mut Age>,
+) {
+    // Also skips NaN lifespans, which `gen_range` rejects
+    if lifespan.max_age.partial_cmp(&0.0) != Some(Ordering::Greater) {
+        return;
+    }
+    for mut age in &mut query {
+        age.0 = rng.0.gen_range(0.0..lifespan.max_age);
+    }
+}
+
+pub(crate) fn age_particles(
+    simulation_time: Res<SimulationTime>,
+    lifespan: Option<Res<Lifespan>>,
+    colors: Res<ParticleColors>,
+    bounds: Res<WorldBounds>,
+    mut rng: ResMut<SimRng>,
+    mut query: Query<(&mut Age, &mut Position, &mut Velocity, &mut ColorId)>,
+) {
+    for (mut age, mut position, mut velocity, mut color) in &mut query {
+        age.0 += simulation_time.delta;
+
+        let Some(lifespan) = &lifespan else {
+            continue;
+        };
+        if age.0 >= lifespan.max_age {
+            let rng = &mut rng.0;
+            age.0 = 0.0;
+            position.0 = real_vec2(Vec2::new(
+                rng.gen_range(bounds.min.x..bounds.max.x),
+                rng.gen_range(bounds.min.y..bounds.max.y),
+            ));
+            velocity.0 = RealVec2::ZERO;
+            color.0 = rng.gen_range(0..colors.0.len());
+        }
+    }
+}
+
+pub(crate) fn fade_particles(
+    lifespan: Option<Res<Lifespan>>,
+    colors: Res<ParticleColors>,
+    handles: Res<ColorHandles>,
+    mut fade_materials: ResMut<FadeMaterials>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut query: Query<(&mut Handle<ColorMaterial>, &Age, &ColorId)>,
+) {
+    let Some(lifespan) = lifespan else {
+        return;
+    };
+    if colors.is_changed() {
+        // A recipe may have changed the colors
+        fade_materials.0.clear();
+    }
+
+    for (mut material, age, color) in &mut query {
+        let level = fade_level(&lifespan, age);
+
+        let new_material = if level >= FADE_LEVELS {
+            handles.0[color.0].clone()
+        } else {
+            fade_materials
+                .0
+                .entry((color.0, level))
+                .or_insert_with(|| {
+                    let mut faded_color = colors.0[color.0];
+                    faded_color.set_a(level as f32 / FADE_LEVELS as f32);
+                    materials.add(ColorMaterial::from(faded_color))
+                })
+                .clone()
+        };
+
+        if *material != new_material {
+            *material = new_material;
+        }
+    }
+}
+
+/// The fade level of a particle of age `age`, from `0` when it is about to respawn to
+/// `FADE_LEVELS` when it isn't fading.
+fn fade_level(lifespan: &Lifespan, age: &Age) -> u8 {
+    // Also catches NaN fade times, which would make the opacity NaN
+    if lifespan.fade_time.partial_cmp(&0.0) != Some(Ordering::Greater) {
+        return FADE_LEVELS;
+    }
+    let remaining = (lifespan.max_age - age.0) / lifespan.fade_time;
+    (remaining.clamp(0.0, 1.0) * FADE_LEVELS as f32) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        advance_steps,
+        clock::{headless_app, test_plugin},
+        Particle, ParticleLifePlugin,
+    };
+
+    #[test]
+    fn initial_ages_are_spread_over_the_lifespan() {
+        let particle = Particle {
+            position: Position::default(),
+            velocity: Velocity::default(),
+            color: ColorId(0),
+        };
+        let lifespan = Lifespan {
+            max_age: 10.0,
+            fade_time: 1.0,
+        };
+        let mut app = headless_app(ParticleLifePlugin {
+            lifespan: Some(lifespan),
+            ..test_plugin(vec![particle; 100])
+        });
+        advance_steps(&mut app, 0.01, 1);
+
+        let ages: Vec<_> = app
+            .world
+            .query::<&Age>()
+            .iter(&app.world)
+            .map(|age| age.0)
+            .collect();
+        assert!(ages
+            .iter()
+            .all(|&age| (0.0..lifespan.max_age).contains(&age)));
+        let young = ages
+            .iter()
+            .filter(|&&age| age < lifespan.max_age / 2.0)
+            .count();
+        assert!((30..70).contains(&young), "{young} young particles");
+    }
+
+    #[test]
+    fn zero_fade_time_does_not_fade() {
+        let lifespan = Lifespan {
+            max_age: 10.0,
+            fade_time: 0.0,
+        };
+        for age in [0.0, 9.5, 10.0, 11.0] {
+            assert_eq!(fade_level(&lifespan, &Age(age)), FADE_LEVELS);
+        }
+
+        let lifespan = Lifespan {
+            fade_time: 1.0,
+            ..lifespan
+        };
+        assert_eq!(fade_level(&lifespan, &Age(0.0)), FADE_LEVELS);
+        assert_eq!(fade_level(&lifespan, &Age(9.5)), FADE_LEVELS / 2);
+        assert_eq!(fade_level(&lifespan, &Age(10.0)), 0);
+    }
+}
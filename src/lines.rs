@@ -0,0 +1,77 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::PrimitiveTopology, view::NoFrustumCulling},
+    sprite::Mesh2dHandle,
+};
+
+/// Line segments to draw this frame. Systems push lines during `CoreStage::Update` and they are
+/// uploaded to a single line-list mesh in `CoreStage::PostUpdate`, after which the buffer is
+/// cleared.
+#[derive(Debug, Clone, Default, Resource)]
+pub(crate) struct Lines {
+    positions: Vec<[f32; 3]>,
+    colors: Vec<[f32; 4]>,
+}
+
+impl Lines {
+    pub(crate) fn line(&mut self, start: Vec2, end: Vec2, color: Color) {
+        let color = color.as_linear_rgba_f32();
+        self.positions.push([start.x, start.y, 0.0]);
+        self.positions.push([end.x, end.y, 0.0]);
+        self.colors.push(color);
+        self.colors.push(color);
+    }
+}
+
+#[derive(Debug, Clone, Default, Resource)]
+struct LinesMeshHandle(Handle<Mesh>);
+
+pub(crate) fn build(app: &mut App) {
+    app.init_resource::<Lines>()
+        .init_resource::<LinesMeshHandle>()
+        .add_startup_system(setup_lines)
+        .add_system_to_stage(CoreStage::PostUpdate, flush_lines);
+}
+
+fn setup_lines(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut handle: ResMut<LinesMeshHandle>,
+) {
+    handle.0 = meshes.add(Mesh::new(PrimitiveTopology::LineList));
+    commands.spawn((
+        ColorMesh2dBundle {
+            mesh: Mesh2dHandle(handle.0.clone()),
+            material: materials.add(ColorMaterial::from(Color::WHITE)),
+            // Slightly behind the particles, which sit at `z = 0`.
+            transform: Transform::from_xyz(0.0, 0.0, -0.05),
+            ..Default::default()
+        },
+        // The mesh changes every frame, so its bounding box would go stale.
+        NoFrustumCulling,
+    ));
+}
+
+fn flush_lines(
+    mut lines: ResMut<Lines>,
+    handle: Res<LinesMeshHandle>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Some(mesh) = meshes.get_mut(&handle.0) else {
+        return;
+    };
+
+    let (mut positions, mut colors) = (
+        std::mem::take(&mut lines.positions),
+        std::mem::take(&mut lines.colors),
+    );
+    // Avoid uploading empty vertex buffers: draw a single invisible, degenerate segment instead.
+    if positions.is_empty() {
+        positions = vec![[0.0; 3]; 2];
+        colors = vec![[0.0; 4]; 2];
+    }
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+}
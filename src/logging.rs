@@ -0,0 +1,190 @@
+//! Structured `tracing` events describing what happens during a session, so that it can be
+//! documented or reproduced from the logs.
+//!
+//! Every event is emitted under the [`LOG_TARGET`] target with an `event` field naming its kind.
+//! Parameter changes are `parameter_changed` events at the `info` level, with the `parameter`
+//! that changed and its `old` and `new` values. Changes to a single entry of the attraction matrix
+//! also carry its `row` and `column`.
+
+use bevy::prelude::*;
+
+use std::fmt::Debug;
+
+use crate::{
+    AdaptiveTimestep, Anisotropy, AttractionHeatmap, AttractionRadius, BondRendering,
+    ColorAttractions, ColorRadiusScale, ComputeBudget, Containment, FlowField, ForceComputation,
+    Friction, Kernel, Lifespan, MaxDelta, PaintBrush, Paused, PeakFraction, QuorumSensing,
+    Reactions, RecenterConfig, ReverseTime, SeamHighlight, SettlePhase, SmoothCutoff,
+    StructuralChangeLimit, TrailBuffer, VelocityTicks,
+};
+
+pub(crate) const LOG_TARGET: &str = "particle_life";
+
+/// Attractions differing by less than this are considered equal when checking that
+/// [`ColorAttractions`] is symmetric.
+const ASYMMETRY_TOLERANCE: f32 = 1e-6;
+
+pub(crate) fn build(app: &mut App) {
+    app.add_startup_system(warn_asymmetric_attractions);
+    app.add_system_set_to_stage(
+        CoreStage::Last,
+        SystemSet::new()
+            .with_system(log_attraction_changes)
+            .with_system(log_changes::<AttractionRadius>("attraction_radius"))
+            .with_system(log_changes::<ColorRadiusScale>("color_radius_scale"))
+            .with_system(log_changes::<PeakFraction>("peak_fraction"))
+            .with_system(log_changes::<Kernel>("kernel"))
+            .with_system(log_changes::<SmoothCutoff>("smooth_cutoff"))
+            .with_system(log_changes::<Anisotropy>("anisotropy"))
+            .with_system(log_changes::<MaxDelta>("max_delta"))
+            .with_system(log_changes::<Paused>("paused"))
+            .with_system(log_changes::<ReverseTime>("reverse_time"))
+            .with_system(log_changes::<ForceComputation>("force_computation"))
+            .with_system(log_changes::<ComputeBudget>("compute_budget"))
+            .with_system(log_changes::<RecenterConfig>("recenter"))
+            .with_system(log_changes::<Containment>("containment"))
+            .with_system(log_changes::<QuorumSensing>("quorum_sensing"))
+            .with_system(log_changes::<Friction>("friction"))
+            .with_system(log_changes::<SettlePhase>("settle_phase"))
+            .with_system(log_changes::<BondRendering>("bond_rendering"))
+            .with_system(log_changes::<SeamHighlight>("seam_highlight"))
+            .with_system(log_changes::<PaintBrush>("paint_brush"))
+            .with_system(log_changes::<StructuralChangeLimit>(
+                "structural_change_limit",
+            ))
+            .with_system(log_changes::<AttractionHeatmap>("heatmap"))
+            .with_system(log_changes::<FlowField>("flow_field"))
+            .with_system(log_changes::<VelocityTicks>("velocity_ticks"))
+            .with_system(log_changes::<TrailBuffer>("trail_buffer"))
+            .with_system(log_changes::<Lifespan>("lifespan"))
+            .with_system(log_changes::<AdaptiveTimestep>("adaptive_timestep"))
+            .with_system(log_changes::<Reactions>("reactions")),
+    );
+}
+
+/// Points out asymmetric attractions at startup, since they make the particles drift as a whole,
+/// which users may not expect. Asymmetric attractions are valid, so this is only a warning.
+fn warn_asymmetric_attractions(color_attractions: Res<ColorAttractions>) {
+    let pairs = color_attractions.asymmetric_pairs(ASYMMETRY_TOLERANCE);
+    if pairs.is_empty() {
+        return;
+    }
+    warn!(
+        target: LOG_TARGET,
+        event = "asymmetric_attractions",
+        pairs = ?pairs,
+        "the attraction matrix is asymmetric, so momentum is not conserved and particles will \
+         drift; make the matrix symmetric or enable `RecenterConfig` to avoid this",
+    );
+}
+
+/// Logs that `parameter` changed from `old` to `new`.
+pub(crate) fn parameter_changed(parameter: &str, old: &dyn Debug, new: &dyn Debug) {
+    info!(
+        target: LOG_TARGET,
+        event = "parameter_changed",
+        parameter,
+        old = ?old,
+        new = ?new,
+    );
+}
+
+/// Returns a system logging every change of the resource `T`, whoever made it. Insertions and
+/// removals of the resource are logged as changes from or to `None`.
+fn log_changes<T: Resource + Clone + PartialEq + Debug>(
+    parameter: &'static str,
+) -> impl FnMut(Option<Res<T>>, Local<Option<Option<T>>>) {
+    move |resource, mut previous| {
+        // Insertions count as changes, but removals have to be told apart from a missing resource
+        let unchanged = match (&resource, &*previous) {
+            (Some(resource), Some(Some(_))) => !resource.is_changed(),
+            (None, Some(None)) => true,
+            _ => false,
+        };
+        if unchanged {
+            return;
+        }
+
+        let current = resource.map(|resource| resource.clone());
+        match &*previous {
+            Some(old) if *old != current => parameter_changed(parameter, old, &current),
+            // Don't log the initial value
+            _ => {}
+        }
+        *previous = Some(current);
+    }
+}
+
+fn log_attraction_changes(
+    color_attractions: Res<ColorAttractions>,
+    mut previous: Local<Option<ColorAttractions>>,
+) {
+    if !color_attractions.is_changed() {
+        return;
+    }
+
+    if let Some(old) = &*previous {
+        let same_shape = old.0.len() == color_attractions.0.len()
+            && old
+                .0
+                .iter()
+                .zip(&color_attractions.0)
+                .all(|(old_row, new_row)| old_row.len() == new_row.len());
+
+        if same_shape {
+            for (row, (old_row, new_row)) in old.0.iter().zip(&color_attractions.0).enumerate() {
+                for (column, (old, new)) in old_row.iter().zip(new_row).enumerate() {
+                    if old != new {
+                        info!(
+                            target: LOG_TARGET,
+                            event = "parameter_changed",
+                            parameter = "color_attractions",
+                            row,
+                            column,
+                            old = old.0,
+                            new = new.0,
+                        );
+                    }
+                }
+            }
+        } else {
+            parameter_changed("color_attractions", old, &*color_attractions);
+        }
+    }
+    *previous = Some(color_attractions.clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Attraction;
+
+    fn attractions(rows: &[&[f32]]) -> ColorAttractions {
+        ColorAttractions(
+            rows.iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&attraction| Attraction(attraction))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn asymmetric_pairs_at_the_tolerance() {
+        // Off by less than the tolerance, which counts as symmetric
+        let symmetric = attractions(&[
+            &[1.0, 0.3, -0.2],
+            &[0.3 + ASYMMETRY_TOLERANCE / 2.0, 0.5, 0.1],
+            &[-0.2, 0.1, 0.0],
+        ]);
+        assert_eq!(symmetric.asymmetric_pairs(ASYMMETRY_TOLERANCE), vec![]);
+
+        let asymmetric = attractions(&[&[1.0, 0.3, -0.2], &[0.3, 0.5, 0.1], &[0.2, -0.1, 0.0]]);
+        assert_eq!(
+            asymmetric.asymmetric_pairs(ASYMMETRY_TOLERANCE),
+            vec![(0, 2), (1, 2)]
+        );
+    }
+}
@@ -1,8 +1,5 @@
 use bevy::prelude::*;
 use particle_life::*;
-use rand::Rng;
-
-use std::iter;
 
 fn main() {
     let mut app = App::new();
@@ -36,92 +33,23 @@ fn init_particle_life() -> ParticleLifePlugin {
 
     let mut rng = rand::thread_rng();
 
-    // let initial_particles = iter::repeat_with(|| Particle {
-    //     position: Position(Vec2::new(
-    //         rng.gen_range(-1.0..1.0),
-    //         rng.gen_range(-1.0..1.0),
-    //     )),
-    //     velocity: Default::default(),
-    //     color: ColorId(rng.gen_range(0..6)),
-    // })
-    // .take(1000)
-    // .collect();
-
     const PARTICLES_PER_COLOR: usize = 200;
 
-    let red_particles: Vec<_> = iter::repeat_with(|| Particle {
-        position: Position(Vec2::new(
-            rng.gen_range(-1.0..-0.75),
-            rng.gen_range(-0.25..0.0),
-        )),
-        velocity: Default::default(),
-        color: ColorId(0),
-    })
-    .take(PARTICLES_PER_COLOR)
-    .collect();
-
-    let green_particles: Vec<_> = iter::repeat_with(|| Particle {
-        position: Position(Vec2::new(
-            rng.gen_range(-0.75..-0.5),
-            rng.gen_range(-0.25..0.0),
-        )),
-        velocity: Default::default(),
-        color: ColorId(1),
-    })
-    .take(PARTICLES_PER_COLOR)
-    .collect();
-
-    let blue_particles: Vec<_> = iter::repeat_with(|| Particle {
-        position: Position(Vec2::new(
-            rng.gen_range(-0.5..-0.25),
-            rng.gen_range(-0.25..0.0),
-        )),
-        velocity: Default::default(),
-        color: ColorId(2),
-    })
-    .take(PARTICLES_PER_COLOR)
-    .collect();
-
-    let yellow_particles: Vec<_> = iter::repeat_with(|| Particle {
-        position: Position(Vec2::new(
-            rng.gen_range(-0.25..0.0),
-            rng.gen_range(-0.25..0.0),
-        )),
-        velocity: Default::default(),
-        color: ColorId(3),
-    })
-    .take(PARTICLES_PER_COLOR)
-    .collect();
-
-    let pink_particles: Vec<_> = iter::repeat_with(|| Particle {
-        position: Position(Vec2::new(
-            rng.gen_range(0.0..0.25),
-            rng.gen_range(-0.25..0.0),
-        )),
-        velocity: Default::default(),
-        color: ColorId(4),
-    })
-    .take(PARTICLES_PER_COLOR)
-    .collect();
-
-    let cyan_particles = iter::repeat_with(|| Particle {
-        position: Position(Vec2::new(
-            rng.gen_range(0.25..0.5),
-            rng.gen_range(-0.25..0.0),
-        )),
-        velocity: Default::default(),
-        color: ColorId(5),
-    })
-    .take(PARTICLES_PER_COLOR);
-
-    let initial_particles = red_particles
-        .into_iter()
-        .chain(green_particles)
-        .chain(blue_particles)
-        .chain(yellow_particles)
-        .chain(pink_particles)
-        .chain(cyan_particles)
-        .collect();
+    // Side by side strips of each color
+    let initial_particles = (0..colors.len())
+        .fold(
+            ParticleSet::new(WorldBounds::default()),
+            |particles, color| {
+                let x = -1.0 + 0.25 * color as f32;
+                particles.rect(
+                    Rect::new(x, -0.25, x + 0.25, 0.0),
+                    PARTICLES_PER_COLOR,
+                    ColorId(color),
+                    &mut rng,
+                )
+            },
+        )
+        .into_particles();
 
     const SELF_ATTRACTION: f32 = 0.3;
     const PREVIOUS_ATTRACTION: f32 = -0.001;
@@ -187,5 +115,6 @@ fn init_particle_life() -> ParticleLifePlugin {
             rmin: 0.04,
             rmax: 0.4,
         },
+        ..Default::default()
     }
 }
@@ -0,0 +1,123 @@
+//! Authoring attraction matrices as images: the grayscale intensity of pixel `(j, i)`, that is
+//! column `j` of row `i`, encodes how much the `i`th color is attracted by the `j`th color.
+
+use image::{ImageBuffer, ImageError, Luma};
+
+use std::{error::Error, fmt, path::Path};
+
+use crate::{Attraction, ColorAttractions, ParticleLifeError};
+
+/// The attractions that pixel intensities from `0` (black) to `1` (white) map to, linearly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AttractionRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Default for AttractionRange {
+    fn default() -> Self {
+        Self {
+            min: -1.0,
+            max: 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AttractionImageError {
+    Image(ImageError),
+    /// The image or matrix doesn't have `expected` rows and columns.
+    DimensionMismatch {
+        expected: usize,
+        width: usize,
+        height: usize,
+    },
+}
+
+impl fmt::Display for AttractionImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Image(error) => write!(f, "{error}"),
+            Self::DimensionMismatch {
+                expected,
+                width,
+                height,
+            } => write!(
+                f,
+                "expected a {expected}x{expected} attraction matrix, found {width}x{height}"
+            ),
+        }
+    }
+}
+
+impl Error for AttractionImageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Image(error) => Some(error),
+            Self::DimensionMismatch { .. } => None,
+        }
+    }
+}
+
+impl From<ImageError> for AttractionImageError {
+    fn from(error: ImageError) -> Self {
+        Self::Image(error)
+    }
+}
+
+/// Reads a `color_count` by `color_count` image as an attraction matrix. Color images are
+/// converted to grayscale.
+pub fn attractions_from_image(
+    path: impl AsRef<Path>,
+    color_count: usize,
+    range: AttractionRange,
+) -> Result<ColorAttractions, ParticleLifeError> {
+    let image = image::open(path)?.into_luma16();
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    if width != color_count || height != color_count {
+        return Err(AttractionImageError::DimensionMismatch {
+            expected: color_count,
+            width,
+            height,
+        }
+        .into());
+    }
+
+    let attractions = image
+        .rows()
+        .map(|row| {
+            row.map(|&Luma([intensity])| {
+                let intensity = intensity as f32 / u16::MAX as f32;
+                Attraction(range.min + intensity * (range.max - range.min))
+            })
+            .collect()
+        })
+        .collect();
+    Ok(ColorAttractions(attractions))
+}
+
+/// Writes an attraction matrix as an image, the inverse of [`attractions_from_image`]. The format
+/// is deduced from the extension of `path`. Attractions outside of `range` are clamped.
+pub fn attractions_to_image(
+    attractions: &ColorAttractions,
+    path: impl AsRef<Path>,
+    range: AttractionRange,
+) -> Result<(), ParticleLifeError> {
+    let size = attractions.0.len();
+    if let Some(row) = attractions.0.iter().find(|row| row.len() != size) {
+        return Err(AttractionImageError::DimensionMismatch {
+            expected: size,
+            width: row.len(),
+            height: size,
+        }
+        .into());
+    }
+
+    let image = ImageBuffer::from_fn(size as u32, size as u32, |x, y| {
+        let attraction = attractions.0[y as usize][x as usize].0;
+        let intensity = ((attraction - range.min) / (range.max - range.min)).clamp(0.0, 1.0);
+        Luma([(intensity * u16::MAX as f32).round() as u16])
+    });
+    image.save(path)?;
+    Ok(())
+}
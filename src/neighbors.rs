@@ -0,0 +1,175 @@
+//! Spatial queries on the particles, for gameplay built on top of the simulation.
+
+use bevy::prelude::*;
+
+use crate::{
+    grid::NeighborGrid,
+    precision::{real, real_vec2},
+    toroidal_dist, update_position, AttractionRadius, Position, Real, WorldBounds,
+};
+
+/// Finds the particles near a point, across the edges of the world.
+///
+/// The particles are bucketed into a grid with cells as large as [`AttractionRadius::rmax`] once
+/// per frame, in `O(n)` for `n` particles, right after they move. Queries see the particles of
+/// all simulations as of that update, so particles spawned or despawned since are missed or
+/// still returned.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct ParticleNeighbors {
+    grid: NeighborGrid,
+    entities: Vec<Entity>,
+    positions: Vec<Position>,
+    bounds: WorldBounds,
+}
+
+impl ParticleNeighbors {
+    /// Returns the particles within `radius` of `point`, in no particular order.
+    ///
+    /// This takes `O(k + r²)` time, where `k` is the number of particles in the cells overlapping
+    /// the disc and `r` is `radius` relative to the cell size.
+    pub fn neighbors_within(&self, point: Vec2, radius: f32) -> Vec<Entity> {
+        let point = self.bounds.wrap(real_vec2(point));
+        let radius = real(radius);
+
+        let mut neighbors = Vec::new();
+        self.grid.for_each_near(point, radius, |index| {
+            if toroidal_dist(point, self.positions[index].0, &self.bounds) <= radius {
+                neighbors.push(self.entities[index]);
+            }
+        });
+        neighbors
+    }
+
+    /// Returns the particle closest to `point`, or `None` if there are no particles.
+    ///
+    /// The search looks in growing squares of cells around `point`, so it takes time proportional
+    /// to the number of particles within twice the distance to the nearest one, and `O(n)` at
+    /// worst when the particles are far away.
+    pub fn nearest(&self, point: Vec2) -> Option<Entity> {
+        if self.entities.is_empty() {
+            return None;
+        }
+        let point = self.bounds.wrap(real_vec2(point));
+        // No two points on the torus are farther apart than half its diagonal
+        let max_distance = real(self.bounds.size().length() / 2.0);
+
+        let mut radius = real(self.bounds.size().min_element()) / 64.0;
+        loop {
+            // Any particle within `radius` is in the cells searched, so the nearest one of them is
+            // the nearest overall
+            let mut nearest: Option<(Real, usize)> = None;
+            self.grid.for_each_near(point, radius, |index| {
+                let distance = toroidal_dist(point, self.positions[index].0, &self.bounds);
+                if distance <= radius && nearest.is_none_or(|(best, _)| distance < best) {
+                    nearest = Some((distance, index));
+                }
+            });
+
+            if let Some((_, index)) = nearest {
+                return Some(self.entities[index]);
+            }
+            if radius >= max_distance {
+                // Unreachable unless positions lie outside of the world
+                return None;
+            }
+            radius *= 2.0;
+        }
+    }
+}
+
+pub(crate) fn build(app: &mut App) {
+    app.init_resource::<ParticleNeighbors>()
+        .add_system(update_neighbors.after(update_position));
+}
+
+fn update_neighbors(
+    attraction_radius: Res<AttractionRadius>,
+    bounds: Res<WorldBounds>,
+    mut neighbors: ResMut<ParticleNeighbors>,
+    query: Query<(Entity, &Position)>,
+) {
+    let neighbors = &mut *neighbors;
+    neighbors.entities.clear();
+    neighbors.positions.clear();
+    for (entity, &position) in &query {
+        neighbors.entities.push(entity);
+        neighbors.positions.push(position);
+    }
+    neighbors.bounds = *bounds;
+    neighbors
+        .grid
+        .rebuild(&neighbors.positions, attraction_radius.rmax, &bounds);
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    fn random_point(rng: &mut StdRng) -> Vec2 {
+        Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0))
+    }
+
+    /// Neighbors of 200 random particles in the default world, with cells `0.2` wide.
+    fn random_neighbors(rng: &mut StdRng) -> ParticleNeighbors {
+        let mut neighbors = ParticleNeighbors::default();
+        for index in 0..200 {
+            neighbors.entities.push(Entity::from_raw(index));
+            neighbors
+                .positions
+                .push(Position(real_vec2(random_point(rng))));
+        }
+        neighbors
+            .grid
+            .rebuild(&neighbors.positions, 0.2, &neighbors.bounds);
+        neighbors
+    }
+
+    fn distances(
+        neighbors: &ParticleNeighbors,
+        point: Vec2,
+    ) -> impl Iterator<Item = (Real, Entity)> + '_ {
+        let point = real_vec2(point);
+        neighbors
+            .positions
+            .iter()
+            .zip(&neighbors.entities)
+            .map(move |(position, &entity)| {
+                (toroidal_dist(point, position.0, &neighbors.bounds), entity)
+            })
+    }
+
+    #[test]
+    fn neighbors_within_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let neighbors = random_neighbors(&mut rng);
+        for _ in 0..100 {
+            let point = random_point(&mut rng);
+            let radius = rng.gen_range(0.0..0.8);
+
+            let mut found = neighbors.neighbors_within(point, radius);
+            found.sort();
+            let mut expected: Vec<_> = distances(&neighbors, point)
+                .filter(|&(distance, _)| distance <= real(radius))
+                .map(|(_, entity)| entity)
+                .collect();
+            expected.sort();
+            assert_eq!(found, expected, "within {radius} of {point}");
+        }
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let neighbors = random_neighbors(&mut rng);
+        for _ in 0..100 {
+            let point = random_point(&mut rng);
+            let expected = distances(&neighbors, point)
+                .min_by(|(distance, _), (other, _)| distance.total_cmp(other))
+                .map(|(_, entity)| entity);
+            assert_eq!(neighbors.nearest(point), expected, "nearest to {point}");
+        }
+        assert_eq!(ParticleNeighbors::default().nearest(Vec2::ZERO), None);
+    }
+}
@@ -0,0 +1,87 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    camera::CursorPosition, precision::real_vec2, replay::Replayer, ColorId, Particle,
+    ParticleColors, Position, SimulationId, SpawnParticle, WorldBounds,
+};
+
+/// Paints particles into the main simulation with the mouse: clicking spawns a particle of the
+/// selected `color` at rest under the cursor, and holding the left button down keeps spawning
+/// `rate` particles per second while dragging. Clicks outside of the world are ignored.
+///
+/// It is toggled and its color is selected with the [`KeyMap`](crate::KeyMap). Painted particles
+/// are recorded like any [`SpawnParticle`], so the brush is disabled while replaying.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct PaintBrush {
+    pub enabled: bool,
+    pub color: ColorId,
+    pub rate: f32,
+}
+
+impl Default for PaintBrush {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: ColorId(0),
+            rate: 30.0,
+        }
+    }
+}
+
+pub(crate) fn build(app: &mut App, paint_brush: PaintBrush) {
+    app.insert_resource(paint_brush).add_system(paint);
+}
+
+/// The input driving the [`PaintBrush`].
+#[derive(SystemParam)]
+struct BrushInput<'w, 's> {
+    time: Res<'w, Time>,
+    buttons: Option<Res<'w, Input<MouseButton>>>,
+    cursor: CursorPosition<'w, 's>,
+}
+
+fn paint(
+    input: BrushInput,
+    replayer: Option<Res<Replayer>>,
+    paint_brush: Res<PaintBrush>,
+    bounds: Res<WorldBounds>,
+    colors: Res<ParticleColors>,
+    mut spawns: EventWriter<SpawnParticle>,
+    // Particles owed by the time held down, to spawn once they add up to a whole one
+    mut pending: Local<f32>,
+) {
+    let Some(buttons) = &input.buttons else {
+        return;
+    };
+    if !paint_brush.enabled || replayer.is_some() || !buttons.pressed(MouseButton::Left) {
+        *pending = 0.0;
+        return;
+    }
+
+    // The first particle is spawned right on click, and the stream follows at the painting rate
+    // in real time, so that painting works while paused
+    if buttons.just_pressed(MouseButton::Left) {
+        *pending = 1.0;
+    } else {
+        *pending += paint_brush.rate * input.time.delta_seconds();
+    }
+    let count = pending.floor();
+    *pending -= count;
+
+    let Some(position) = input.cursor.world_position() else {
+        return;
+    };
+    if !bounds.contains(position) || paint_brush.color.0 >= colors.0.len() {
+        return;
+    }
+    for _ in 0..count as usize {
+        spawns.send(SpawnParticle {
+            particle: Particle {
+                position: Position(real_vec2(position)),
+                velocity: Default::default(),
+                color: paint_brush.color,
+            },
+            simulation: SimulationId::MAIN,
+        });
+    }
+}
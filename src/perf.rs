@@ -0,0 +1,121 @@
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+    utils::Instant,
+};
+
+/// Shows the frame rate, the frame time, and how much of it is spent in the physics systems in
+/// the top-left corner of the window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub struct PerfOverlay {
+    pub enabled: bool,
+}
+
+/// Time spent computing the forces between particles on the main thread each frame, in
+/// milliseconds.
+pub const PHYSICS_TIME: DiagnosticId =
+    DiagnosticId::from_u128(0x5d1c_6e2b_84a3_4f7e_9b0d_2c61_f3a8_e047);
+
+const FONT_SIZE: f32 = 16.0;
+
+/// Marks the text of the overlay.
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct PerfText;
+
+pub(crate) fn build(app: &mut App, perf_overlay: PerfOverlay) {
+    if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+        app.add_plugin(FrameTimeDiagnosticsPlugin);
+    }
+
+    app.insert_resource(perf_overlay)
+        .add_startup_system(setup_physics_diagnostic)
+        .add_startup_system(setup_perf_text)
+        .add_system(update_perf_text);
+}
+
+/// Measures the time spent computing forces, recorded as a [`PHYSICS_TIME`] measurement.
+pub(crate) struct PhysicsTimer {
+    start: Instant,
+}
+
+impl PhysicsTimer {
+    pub(crate) fn start() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+
+    pub(crate) fn stop(self, diagnostics: Option<ResMut<Diagnostics>>) {
+        if let Some(mut diagnostics) = diagnostics {
+            let elapsed = self.start.elapsed().as_secs_f64() * 1000.0;
+            diagnostics.add_measurement(PHYSICS_TIME, || elapsed);
+        }
+    }
+}
+
+fn setup_physics_diagnostic(diagnostics: Option<ResMut<Diagnostics>>) {
+    if let Some(mut diagnostics) = diagnostics {
+        diagnostics.add(Diagnostic::new(PHYSICS_TIME, "physics_time", 20).with_suffix("ms"));
+    }
+}
+
+/// Adds the monospace font embedded in the crate, for text overlays.
+pub(crate) fn add_overlay_font(fonts: &mut Assets<Font>) -> Handle<Font> {
+    let font = Font::try_from_bytes(include_bytes!("../assets/fonts/DejaVuSansMono.ttf").to_vec())
+        .expect("the embedded font is valid");
+    fonts.add(font)
+}
+
+fn setup_perf_text(mut commands: Commands, mut fonts: ResMut<Assets<Font>>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: add_overlay_font(&mut fonts),
+                font_size: FONT_SIZE,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(8.0),
+                top: Val::Px(8.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+        PerfText,
+    ));
+}
+
+fn update_perf_text(
+    perf_overlay: Res<PerfOverlay>,
+    diagnostics: Res<Diagnostics>,
+    mut query: Query<(&mut Text, &mut Visibility), With<PerfText>>,
+) {
+    for (mut text, mut visibility) in &mut query {
+        visibility.is_visible = perf_overlay.enabled;
+        if !perf_overlay.enabled {
+            continue;
+        }
+
+        let smoothed = |id| {
+            diagnostics
+                .get(id)
+                .and_then(Diagnostic::smoothed)
+                .unwrap_or(0.0)
+        };
+        let fps = smoothed(FrameTimeDiagnosticsPlugin::FPS);
+        let frame_time = smoothed(FrameTimeDiagnosticsPlugin::FRAME_TIME);
+        let physics_time = smoothed(PHYSICS_TIME);
+
+        text.sections[0].value = format!(
+            "FPS      {fps:>7.1}\n\
+             frame    {frame_time:>7.2} ms\n\
+             physics  {physics_time:>7.2} ms\n\
+             other    {:>7.2} ms",
+            (frame_time - physics_time).max(0.0),
+        );
+    }
+}
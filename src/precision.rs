@@ -0,0 +1,141 @@
+//! The precision of the physics state.
+//!
+//! [`Position`](crate::Position)s, [`Velocity`](crate::Velocity)s and the force computations use
+//! `f32` by default, which drifts over long-running simulations. The `double-precision` feature
+//! switches them to `f64`, at the cost of a slower force computation and of converting every
+//! position back to `f32` for rendering.
+
+use bevy::prelude::*;
+
+/// The scalar type of the physics state.
+#[cfg(not(feature = "double-precision"))]
+pub type Real = f32;
+/// The scalar type of the physics state.
+#[cfg(feature = "double-precision")]
+pub type Real = f64;
+
+/// The vector type of the physics state.
+#[cfg(not(feature = "double-precision"))]
+pub type RealVec2 = Vec2;
+/// The vector type of the physics state.
+#[cfg(feature = "double-precision")]
+pub type RealVec2 = bevy::math::DVec2;
+
+/// Converts a single-precision scalar to the precision of the physics state.
+#[cfg(not(feature = "double-precision"))]
+pub(crate) fn real(x: f32) -> Real {
+    x
+}
+/// Converts a single-precision scalar to the precision of the physics state.
+#[cfg(feature = "double-precision")]
+pub(crate) fn real(x: f32) -> Real {
+    x.into()
+}
+
+/// Converts a double-precision scalar to the precision of the physics state.
+#[cfg(all(feature = "binary-snapshots", not(feature = "double-precision")))]
+pub(crate) fn real_from_f64(x: f64) -> Real {
+    x as f32
+}
+/// Converts a double-precision scalar to the precision of the physics state.
+#[cfg(all(feature = "binary-snapshots", feature = "double-precision"))]
+pub(crate) fn real_from_f64(x: f64) -> Real {
+    x
+}
+
+/// Converts a single-precision vector to the precision of the physics state.
+#[cfg(not(feature = "double-precision"))]
+pub(crate) fn real_vec2(v: Vec2) -> RealVec2 {
+    v
+}
+/// Converts a single-precision vector to the precision of the physics state.
+#[cfg(feature = "double-precision")]
+pub(crate) fn real_vec2(v: Vec2) -> RealVec2 {
+    v.as_dvec2()
+}
+
+/// Converts a scalar of the physics state to single precision, for rendering.
+#[cfg(not(feature = "double-precision"))]
+pub(crate) fn single(x: Real) -> f32 {
+    x
+}
+/// Converts a scalar of the physics state to single precision, for rendering.
+#[cfg(feature = "double-precision")]
+pub(crate) fn single(x: Real) -> f32 {
+    x as f32
+}
+
+/// Converts a vector of the physics state to single precision, for rendering.
+#[cfg(not(feature = "double-precision"))]
+pub(crate) fn single_vec2(v: RealVec2) -> Vec2 {
+    v
+}
+/// Converts a vector of the physics state to single precision, for rendering.
+#[cfg(feature = "double-precision")]
+pub(crate) fn single_vec2(v: RealVec2) -> Vec2 {
+    v.as_vec2()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        advance_steps,
+        clock::{headless_app, test_plugin},
+        spawn_in_rect, Attraction, ColorAttractions, ColorId, Position, ReverseTime, WorldBounds,
+    };
+
+    /// How far the particles end up from where they started after running the simulation
+    /// forward, then backward for as long. Velocity Verlet is time-reversible, so this only
+    /// measures the accumulated rounding errors.
+    fn round_trip_drift(steps: usize) -> Real {
+        let bounds = WorldBounds::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let particles: Vec<_> = [ColorId(0), ColorId(1)]
+            .into_iter()
+            .flat_map(|color| {
+                let region = Rect::new(-0.5, -0.5, 0.5, 0.5);
+                spawn_in_rect(region, 100, color, &bounds, &mut rng)
+            })
+            .collect();
+        let mut app = headless_app(crate::ParticleLifePlugin {
+            color_attractions: ColorAttractions(vec![
+                vec![Attraction(0.5), Attraction(-0.2)],
+                vec![Attraction(0.3), Attraction(0.1)],
+            ]),
+            ..test_plugin(particles.clone())
+        });
+
+        advance_steps(&mut app, 0.01, steps);
+        app.insert_resource(ReverseTime(true));
+        advance_steps(&mut app, 0.01, steps);
+
+        let mut query = app.world.query::<(&crate::ParticleIndex, &Position)>();
+        query
+            .iter(&app.world)
+            .map(|(index, position)| {
+                let start = particles[index.0 as usize].position.0;
+                crate::toroidal_dist(start, position.0, &bounds)
+            })
+            .fold(0.0, Real::max)
+    }
+
+    /// Brackets the drift of [`round_trip_drift`] over 200 steps in each precision, about `3e-4`
+    /// in single precision and `1e-12` in double precision, so that a build drifting like the
+    /// other precision fails either way.
+    #[cfg(not(feature = "double-precision"))]
+    const DRIFT_BOUNDS: std::ops::Range<Real> = 1e-5..1e-2;
+    #[cfg(feature = "double-precision")]
+    const DRIFT_BOUNDS: std::ops::Range<Real> = 1e-14..1e-9;
+
+    #[test]
+    fn double_precision_drifts_less_than_single_precision() {
+        let drift = round_trip_drift(200);
+        assert!(
+            DRIFT_BOUNDS.contains(&drift),
+            "drift {drift:e} outside of {DRIFT_BOUNDS:?}"
+        );
+    }
+}
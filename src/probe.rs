@@ -0,0 +1,272 @@
+use bevy::prelude::*;
+
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    grid::NeighborGrid, logging::LOG_TARGET, ColorId, ForceSettings, Position, RealVec2,
+    SimulationId, SimulationTime, Velocity,
+};
+
+/// The particle whose state is recorded into [`ProbeRecord`] every frame, if any.
+///
+/// Setting it starts a new recording, while clearing it stops recording and keeps the samples
+/// recorded so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub struct ProbedEntity(pub Option<Entity>);
+
+/// How the state of the [`ProbedEntity`] is recorded.
+#[derive(Debug, Clone, PartialEq, Resource)]
+pub struct ProbeConfig {
+    /// How many of the latest samples are kept in the [`ProbeRecord`].
+    pub capacity: usize,
+    /// A CSV file to also write every sample to, truncated whenever a new recording starts.
+    pub csv_path: Option<PathBuf>,
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            csv_path: None,
+        }
+    }
+}
+
+/// The state of the probed particle at the end of a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProbeSample {
+    /// The [`SimulationTime::elapsed`] of the frame.
+    pub time: f32,
+    pub position: RealVec2,
+    pub velocity: RealVec2,
+    /// The acceleration the particle gets from its current position, as applied by the physics:
+    /// the forces of the other particles, with the [`Anisotropy`](crate::Anisotropy), the
+    /// [`QuorumSensing`](crate::QuorumSensing) and the topology of the world, plus the
+    /// [`Containment`](crate::Containment).
+    pub net_force: RealVec2,
+}
+
+/// The latest samples of the [`ProbedEntity`], oldest first.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct ProbeRecord {
+    pub entity: Option<Entity>,
+    pub samples: VecDeque<ProbeSample>,
+}
+
+#[derive(Default)]
+struct Recording {
+    entity: Option<Entity>,
+    csv: Option<BufWriter<File>>,
+}
+
+pub(crate) fn build(app: &mut App, config: ProbeConfig) {
+    app.init_resource::<ProbedEntity>()
+        .init_resource::<ProbeRecord>()
+        .insert_resource(config)
+        // After the physics of the frame
+        .add_system_to_stage(CoreStage::PostUpdate, record_probe);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_probe(
+    probed: Res<ProbedEntity>,
+    config: Res<ProbeConfig>,
+    simulation_time: Res<SimulationTime>,
+    force_settings: ForceSettings,
+    mut record: ResMut<ProbeRecord>,
+    mut recording: Local<Recording>,
+    mut grid: Local<NeighborGrid>,
+    query: Query<(Entity, &Position, &Velocity, &ColorId, &SimulationId)>,
+) {
+    let Some(entity) = probed.0 else {
+        *recording = Recording::default();
+        return;
+    };
+    if recording.entity != Some(entity) {
+        record.entity = Some(entity);
+        record.samples.clear();
+        recording.entity = Some(entity);
+        recording.csv = config.csv_path.as_ref().and_then(|path| {
+            create_csv(path)
+                .map_err(|error| {
+                    warn!(target: LOG_TARGET, event = "probe_failed", ?path, %error);
+                })
+                .ok()
+        });
+    }
+
+    let Ok((_, &position, &velocity, ..)) = query.get(entity) else {
+        return;
+    };
+    let mut index = 0;
+    let (mut positions, mut colors, mut simulations) = (Vec::new(), Vec::new(), Vec::new());
+    for (other, &position, _, &color, &simulation) in &query {
+        if other == entity {
+            index = positions.len();
+        }
+        positions.push(position);
+        colors.push(color);
+        simulations.push(simulation);
+    }
+    // The same acceleration as `update_velocity` gives the particle
+    let force_models = force_settings.force_models();
+    force_models.rebuild_grid(&positions, &mut grid);
+    let (net_force, _) =
+        force_models.full_acceleration(index, &positions, &colors, &simulations, &grid);
+
+    let sample = ProbeSample {
+        time: simulation_time.elapsed,
+        position: position.0,
+        velocity: velocity.0,
+        net_force,
+    };
+    while record.samples.len() >= config.capacity.max(1) {
+        record.samples.pop_front();
+    }
+    record.samples.push_back(sample);
+
+    if let Some(csv) = &mut recording.csv {
+        if let Err(error) = write_sample(csv, &sample) {
+            warn!(target: LOG_TARGET, event = "probe_failed", %error);
+            recording.csv = None;
+        }
+    }
+}
+
+fn create_csv(path: &Path) -> io::Result<BufWriter<File>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "time,x,y,vx,vy,fx,fy")?;
+    Ok(writer)
+}
+
+fn write_sample(writer: &mut impl Write, sample: &ProbeSample) -> io::Result<()> {
+    let ProbeSample {
+        time,
+        position,
+        velocity,
+        net_force,
+    } = sample;
+    writeln!(
+        writer,
+        "{time},{},{},{},{},{},{}",
+        position.x, position.y, velocity.x, velocity.y, net_force.x, net_force.y
+    )?;
+    // The recording has no end to flush at, and the app may be closed or killed at any point
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        advance_steps,
+        clock::{headless_app, test_plugin},
+        spawn_in_rect, Acceleration, Anisotropy, Attraction, ColorAttractions, Containment,
+        Particle, ParticleLifePlugin, QuorumSensing, Topology, WorldBounds,
+    };
+
+    #[test]
+    fn net_force_is_the_acceleration_of_the_physics() {
+        let bounds = WorldBounds {
+            topology: Topology::Klein,
+            ..default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut particles = spawn_in_rect(
+            Rect::new(-1.0, -1.0, 1.0, 1.0),
+            300,
+            ColorId(0),
+            &bounds,
+            &mut rng,
+        );
+        particles.extend(spawn_in_rect(
+            Rect::new(-1.0, -1.0, 1.0, 1.0),
+            300,
+            ColorId(1),
+            &bounds,
+            &mut rng,
+        ));
+        let mut app = headless_app(ParticleLifePlugin {
+            bounds,
+            color_attractions: ColorAttractions(vec![
+                vec![Attraction(1.0), Attraction(-0.4)],
+                vec![Attraction(0.6), Attraction(0.2)],
+            ]),
+            anisotropy: Anisotropy { x: 1.5, y: 0.8 },
+            containment: Containment {
+                radius: 0.3,
+                strength: 2.0,
+                enabled: true,
+            },
+            quorum_sensing: Some(QuorumSensing {
+                threshold: 3,
+                below: 1.0,
+                above: -0.5,
+            }),
+            ..test_plugin(particles)
+        });
+        advance_steps(&mut app, 0.01, 2);
+
+        let entities: Vec<_> = app
+            .world
+            .query_filtered::<Entity, With<Position>>()
+            .iter(&app.world)
+            .step_by(50)
+            .collect();
+        for entity in entities {
+            app.insert_resource(ProbedEntity(Some(entity)));
+            advance_steps(&mut app, 0.01, 1);
+
+            let net_force = app
+                .world
+                .resource::<ProbeRecord>()
+                .samples
+                .back()
+                .unwrap()
+                .net_force;
+            let acceleration = app.world.get::<Acceleration>(entity).unwrap().0;
+            assert!(
+                (net_force - acceleration).length() < 1e-4,
+                "{entity:?}: net force {net_force} instead of {acceleration}"
+            );
+        }
+    }
+
+    #[test]
+    fn samples_are_written_to_the_csv_as_they_come() {
+        let path =
+            std::env::temp_dir().join(format!("particle_life_probe_{}.csv", std::process::id()));
+        let particle = Particle {
+            position: Position(RealVec2::ZERO),
+            velocity: Velocity(RealVec2::X),
+            color: ColorId(0),
+        };
+        let mut app = headless_app(ParticleLifePlugin {
+            probe: ProbeConfig {
+                csv_path: Some(path.clone()),
+                ..default()
+            },
+            ..test_plugin(vec![particle])
+        });
+        let entity = app
+            .world
+            .query_filtered::<Entity, With<Position>>()
+            .single(&app.world);
+        app.insert_resource(ProbedEntity(Some(entity)));
+        advance_steps(&mut app, 0.01, 3);
+
+        // Read while the app is still running
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 4, "{csv}");
+        assert_eq!(lines[0], "time,x,y,vx,vy,fx,fy");
+    }
+}
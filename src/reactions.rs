@@ -0,0 +1,134 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    background, grid::NeighborGrid, precision::real, toroidal_dist, update_velocity, ColorId,
+    ParticleLifeSystem, Position, SimulationId, SimulationTime, WorldBounds,
+};
+
+/// Turns a pair of particles of colors `reactants` into particles of colors `products` once they
+/// have stayed in contact for `duration` seconds, the particle of color `reactants.0` becoming of
+/// color `products.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reaction {
+    pub reactants: (ColorId, ColorId),
+    pub duration: f32,
+    pub products: (ColorId, ColorId),
+}
+
+/// Changes the colors of particles that stay close to each other, for chemistry-like simulations.
+///
+/// Two particles of the same simulation are in contact while they are closer than
+/// `contact_distance`. The contact time of a pair starts over whenever they move apart, and a
+/// particle takes part in at most one reaction per frame. When several reactions apply to the same
+/// reactants, the first one in `reactions` wins.
+#[derive(Debug, Clone, PartialEq, Resource)]
+pub struct Reactions {
+    pub reactions: Vec<Reaction>,
+    pub contact_distance: f32,
+}
+
+impl Default for Reactions {
+    fn default() -> Self {
+        Self {
+            reactions: Vec::new(),
+            contact_distance: 0.02,
+        }
+    }
+}
+
+impl Reactions {
+    /// Returns the reaction between particles of colors `a` and `b`, with the reactants swapped if
+    /// `b` is the first reactant.
+    fn find(&self, a: ColorId, b: ColorId) -> Option<Reaction> {
+        self.reactions.iter().find_map(|&reaction| {
+            if reaction.reactants == (a, b) {
+                Some(reaction)
+            } else if reaction.reactants == (b, a) {
+                Some(Reaction {
+                    reactants: (a, b),
+                    duration: reaction.duration,
+                    products: (reaction.products.1, reaction.products.0),
+                })
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// How long each pair of particles in contact has been, keyed by their entities in increasing
+/// order.
+#[derive(Debug, Clone, Default, Resource)]
+struct ContactTimes(HashMap<(Entity, Entity), f32>);
+
+pub(crate) fn build(app: &mut App, reactions: Reactions) {
+    app.insert_resource(reactions)
+        .init_resource::<ContactTimes>()
+        .add_system(
+            react
+                .after(update_velocity)
+                .after(background::update_velocity_in_background)
+                .before(ParticleLifeSystem::UpdateMaterial),
+        );
+}
+
+fn react(
+    simulation_time: Res<SimulationTime>,
+    reactions: Res<Reactions>,
+    bounds: Res<WorldBounds>,
+    mut contact_times: ResMut<ContactTimes>,
+    mut grid: Local<NeighborGrid>,
+    mut query: Query<(Entity, &Position, &mut ColorId, &SimulationId)>,
+) {
+    if reactions.reactions.is_empty() {
+        contact_times.0.clear();
+        return;
+    }
+
+    let (mut entities, mut positions, mut colors, mut simulations) =
+        (vec![], vec![], vec![], vec![]);
+    for (entity, &position, &color, &simulation) in &query {
+        entities.push(entity);
+        positions.push(position);
+        colors.push(color);
+        simulations.push(simulation);
+    }
+    grid.rebuild(&positions, reactions.contact_distance, &bounds);
+
+    // Pairs that moved apart since the previous frame are forgotten
+    let mut previous_times = std::mem::take(&mut contact_times.0);
+    let mut reacted = vec![false; entities.len()];
+    let mut products = Vec::new();
+    grid.for_each_pair(|a, b| {
+        if simulations[a] != simulations[b]
+            || toroidal_dist(positions[a].0, positions[b].0, &bounds)
+                >= real(reactions.contact_distance)
+        {
+            return;
+        }
+        let Some(reaction) = reactions.find(colors[a], colors[b]) else {
+            return;
+        };
+
+        let key = if entities[a] < entities[b] {
+            (entities[a], entities[b])
+        } else {
+            (entities[b], entities[a])
+        };
+        let time = previous_times.remove(&key).unwrap_or(0.0) + simulation_time.delta;
+        if time >= reaction.duration && !reacted[a] && !reacted[b] {
+            reacted[a] = true;
+            reacted[b] = true;
+            products.push((entities[a], reaction.products.0));
+            products.push((entities[b], reaction.products.1));
+        } else {
+            contact_times.0.insert(key, time);
+        }
+    });
+
+    for (entity, product) in products {
+        if let Ok((_, _, mut color, _)) = query.get_mut(entity) {
+            *color = product;
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Recipes: the colors, attraction matrix and attraction radius of the main simulation, without
+//! its particles, to share an interesting set of parameters.
+//!
+//! Recipes are saved as [configuration files](crate::config) with only these settings:
+//!
+//! ```text
+//! colors #ff0000ff #00ff00ff
+//! color_attractions 0.3,-0.1;0.2,0.3
+//! attraction_radius 0.04 0.4
+//! ```
+//!
+//! so any configuration file setting them can also be loaded as a recipe, its other settings
+//! being ignored.
+
+use bevy::prelude::*;
+
+use std::{fmt, io, path::Path, str::FromStr};
+
+use crate::{
+    config::set_attractions,
+    logging::LOG_TARGET,
+    svg::hex,
+    validation::{check_matrix, check_radius, check_reactions},
+    AttractionRadius, ColorAttractions, ColorHandles, ColorId, ConfigError, ConfigFileError,
+    ConfigProblem, PartialConfig, ParticleColors, ParticleLifeError, Reactions, ReplayEvent,
+    SimulationId, SimulationOverrides,
+};
+
+/// The parameters of a recipe, see the [module documentation](self) for its format.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recipe {
+    pub colors: Vec<Color>,
+    pub color_attractions: ColorAttractions,
+    pub attraction_radius: AttractionRadius,
+}
+
+impl Recipe {
+    /// Captures the recipe of the main simulation from `world`.
+    pub fn capture(world: &World) -> Self {
+        Self {
+            colors: world.resource::<ParticleColors>().0.clone(),
+            color_attractions: world.resource::<ColorAttractions>().clone(),
+            attraction_radius: *world.resource(),
+        }
+    }
+
+    /// Checks that there are colors, that the attraction matrix has a row and a column per color
+    /// and that the attraction radius has `rmin < rmax`.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let colors = self.colors.len();
+        let mut problems = Vec::new();
+        if colors == 0 {
+            problems.push(ConfigProblem::NoColors);
+        }
+        check_matrix(
+            SimulationId::MAIN,
+            &self.color_attractions,
+            colors,
+            &mut problems,
+        );
+        check_radius(SimulationId::MAIN, self.attraction_radius, &mut problems);
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError(problems))
+        }
+    }
+
+    /// Replaces the recipe of the running simulation in `world`, leaving the particles in place.
+    ///
+    /// When the recipe has fewer colors than the simulation, particles with the `i`th color get
+    /// the `i % n`th color of the `n` colors of the recipe, and the other particles keep their
+    /// color. Extra simulations keep their own attraction matrices.
+    ///
+    /// Fails without changing anything when the recipe is invalid, when the [`Reactions`] involve
+    /// colors out of its palette, or when the attraction matrix of an extra simulation doesn't have
+    /// a row and a column per color of the recipe.
+    pub fn apply(self, world: &mut World) -> Result<(), ConfigError> {
+        self.validate()?;
+        self.check_against(world)?;
+
+        let count = self.colors.len();
+        let mut remapped = 0;
+        for mut color in world.query::<&mut ColorId>().iter_mut(world) {
+            if color.0 >= count {
+                color.0 %= count;
+                remapped += 1;
+            }
+        }
+
+        // Recolor the existing materials in place so that particles keeping their color follow.
+        // Headless apps have no materials.
+        if world.contains_resource::<ColorHandles>() {
+            world.resource_scope(|world, mut materials: Mut<Assets<ColorMaterial>>| {
+                let mut handles = world.resource_mut::<ColorHandles>();
+                handles.0.truncate(count);
+                for (i, &color) in self.colors.iter().enumerate() {
+                    match handles.0.get(i) {
+                        Some(handle) => {
+                            if let Some(material) = materials.get_mut(handle) {
+                                material.color = color;
+                            }
+                        }
+                        None => handles.0.push(materials.add(ColorMaterial::from(color))),
+                    }
+                }
+            });
+        }
+
+        world.insert_resource(ParticleColors(self.colors));
+        world.insert_resource(self.color_attractions);
+        world.insert_resource(self.attraction_radius);
+        info!(
+            target: LOG_TARGET,
+            event = "recipe_loaded",
+            colors = count,
+            remapped
+        );
+        Ok(())
+    }
+
+    /// Checks the settings of `world` that depend on the palette against the recipe.
+    fn check_against(&self, world: &World) -> Result<(), ConfigError> {
+        let colors = self.colors.len();
+        let mut problems = Vec::new();
+        if let Some(reactions) = world.get_resource::<Reactions>() {
+            check_reactions(reactions, colors, &mut problems);
+        }
+        if let Some(overrides) = world.get_resource::<SimulationOverrides>() {
+            // In a fixed order, so that the error doesn't depend on the hash order
+            let mut overrides: Vec<_> = overrides.0.iter().collect();
+            overrides.sort_by_key(|&(&simulation, _)| simulation);
+            for (&simulation, settings) in overrides {
+                if let Some(color_attractions) = &settings.color_attractions {
+                    check_matrix(simulation, color_attractions, colors, &mut problems);
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError(problems))
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ParticleLifeError> {
+        Ok(std::fs::read_to_string(path)?.parse::<Self>()?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Recipe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "colors")?;
+        for &color in &self.colors {
+            write!(f, " {}", hex(color))?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{}",
+            ReplayEvent::ColorAttractions(self.color_attractions.clone())
+        )?;
+        writeln!(
+            f,
+            "{}",
+            ReplayEvent::AttractionRadius(self.attraction_radius)
+        )
+    }
+}
+
+impl FromStr for Recipe {
+    type Err = ConfigFileError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let PartialConfig {
+            colors,
+            color_attractions,
+            attractions,
+            attraction_radius,
+            ..
+        } = s.parse()?;
+        let mut color_attractions =
+            color_attractions.ok_or(ConfigFileError::MissingSetting("color_attractions"))?;
+        set_attractions(&mut color_attractions, attractions);
+        Ok(Self {
+            colors: colors.ok_or(ConfigFileError::MissingSetting("colors"))?,
+            color_attractions,
+            attraction_radius: attraction_radius
+                .ok_or(ConfigFileError::MissingSetting("attraction_radius"))?,
+        })
+    }
+}
+
+/// Saves the recipe of the running simulation in `world` to `path`.
+pub fn save_recipe(world: &World, path: impl AsRef<Path>) -> io::Result<()> {
+    Recipe::capture(world).save(path)
+}
+
+/// Loads the recipe at `path` into the running simulation in `world`, see [`Recipe::apply`].
+///
+/// This reads the file synchronously, like
+/// [`load_config_with_overrides`](crate::load_config_with_overrides).
+pub fn load_recipe(world: &mut World, path: impl AsRef<Path>) -> Result<(), ParticleLifeError> {
+    Recipe::load(path)?.apply(world)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        advance_steps,
+        clock::{headless_app, test_plugin},
+        Attraction, Particle, ParticleLifePlugin, Position, Reaction, Velocity,
+    };
+
+    #[test]
+    fn smaller_recipes_are_checked_against_the_reactions() {
+        let particles = (0..4)
+            .map(|i| Particle {
+                position: Position::default(),
+                velocity: Velocity::default(),
+                color: ColorId(i % 2),
+            })
+            .collect();
+        let mut app = headless_app(ParticleLifePlugin {
+            reactions: Reactions {
+                reactions: vec![Reaction {
+                    reactants: (ColorId(0), ColorId(0)),
+                    duration: 1.0,
+                    products: (ColorId(1), ColorId(1)),
+                }],
+                ..Default::default()
+            },
+            ..test_plugin(particles)
+        });
+        advance_steps(&mut app, 0.01, 1);
+        let recipe = Recipe {
+            colors: vec![Color::BLUE],
+            color_attractions: ColorAttractions(vec![vec![Attraction(0.5)]]),
+            attraction_radius: AttractionRadius {
+                rmin: 0.05,
+                rmax: 0.3,
+            },
+        };
+
+        let error = recipe.clone().apply(&mut app.world).unwrap_err();
+        assert_eq!(
+            error,
+            ConfigError(vec![ConfigProblem::ReactionColorOutOfRange {
+                index: 0,
+                colors: 1,
+                color: ColorId(1),
+            }])
+        );
+        assert_eq!(app.world.resource::<ParticleColors>().0.len(), 2);
+
+        app.world.resource_mut::<Reactions>().reactions.clear();
+        recipe.apply(&mut app.world).unwrap();
+        advance_steps(&mut app, 0.01, 1);
+        let mut query = app.world.query::<&ColorId>();
+        assert!(query.iter(&app.world).all(|color| color.0 == 0));
+    }
+}
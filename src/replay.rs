@@ -0,0 +1,970 @@
+//! Recording sessions to replay them deterministically, to reproduce a result or make a video.
+//!
+//! While recording, the changes of the simulation parameters, the particles spawned and despawned
+//! with [`SpawnParticle`] and [`DespawnParticle`], such as those painted with the
+//! [`PaintBrush`](crate::PaintBrush) or respawned by [`ResetParticles`](crate::ResetParticles), and
+//! the [`StepSimulation`] and [`ShuffleColors`] events are written to a [`ReplayLog`] along with
+//! the frame they happened in. The first frame records the value of every parameter, so that the
+//! replay doesn't depend on the settings of its plugin. While replaying, they are fed back at the
+//! same frames, with the [`SimRng`](crate::SimRng) seeded from the log. Both modes advance the
+//! simulation by a fixed time step of [`MaxDelta`] every frame, so that the physics doesn't depend
+//! on the frame rate.
+//!
+//! Changes are captured in [`CoreStage::PreUpdate`], after the keyboard shortcuts and before the
+//! time step, and replayed in [`CoreStage::First`] of the same frame, so that the parameters
+//! inserted or removed are in place for the time step. The run is reproduced exactly as long as the
+//! parameters are changed outside of [`CoreStage::Update`], where the simulation step runs, for
+//! instance by the shortcuts or between calls to [`App::update`]: changes made by systems of that
+//! stage may take effect one frame later when replayed. Spawns and despawns always take effect at
+//! the same frame. The initial particles are not part of the log and should be the same for both
+//! runs, along with the [`Lifespan`] of the plugin, which randomizes their initial ages. Particles
+//! spawned with [`spawn_particles_with`] aren't recorded, and [`ForceComputation::Background`]
+//! isn't deterministic, so neither should be used.
+//!
+//! # Format
+//!
+//! The log is a text file starting with a `seed <u64>` line, followed by one line per event:
+//! the frame number, the kind of the event, then its arguments, separated by spaces. Empty lines
+//! and lines starting with `#` are ignored.
+//!
+//! ```text
+//! seed 42
+//! 0 max_delta 0.033333335
+//! 0 lifespan none
+//! 120 paused true
+//! 121 step
+//! 130 shuffle_colors true
+//! 200 attraction_radius 0.04 0.4
+//! 220 color_radius_scale 1,0.5
+//! 250 kernel smoothstep
+//! 260 force_computation parallel
+//! 280 settle_phase 2 5
+//! 300 color_attractions 0.3,-0.1;0.2,0.3
+//! 305 containment true 0.5 1
+//! 306 anisotropy 2 1
+//! 307 quorum_sensing 8 1 -0.5
+//! 308 smooth_cutoff 0.2
+//! 309 compute_budget 20000
+//! 309 adaptive_timestep 0.004 0.033 0.005
+//! 310 spawn 0 0.1 -0.2 0 0 1
+//! 320 despawn 12
+//! ```
+//!
+//! The rows of `color_attractions` are separated by `;` and their entries by `,`, and optional
+//! parameters such as `lifespan` are `none` when disabled. `spawn` takes the simulation of the
+//! particle, its position, its velocity and its color, and `despawn` the [`ParticleIndex`] of the
+//! particle.
+//!
+//! [`ForceComputation::Background`]: crate::ForceComputation::Background
+//! [`spawn_particles_with`]: crate::spawn_particles_with
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use std::{
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::{FromStr, SplitWhitespace},
+};
+
+use crate::{
+    apply_structural_changes, keymap::handle_shortcuts, logging::LOG_TARGET,
+    reset::reset_particles, update_simulation_time, AdaptiveTimestep, Anisotropy, Attraction,
+    AttractionRadius, ColorAttractions, ColorId, ColorRadiusScale, ComputeBudget, Containment,
+    DespawnParticle, ForceComputation, Friction, Kernel, Lifespan, MaxDelta, Particle,
+    ParticleIndex, ParticleLifeError, Paused, PeakFraction, Position, QuorumSensing, RealVec2,
+    RecenterConfig, ReverseTime, SettlePhase, ShuffleColors, SimulationId, SmoothCutoff,
+    SpawnParticle, StepSimulation, Topology, Velocity,
+};
+
+/// Whether the session is recorded or replayed.
+#[derive(Debug, Clone, Default)]
+pub enum ReplayMode {
+    #[default]
+    Off,
+    /// Records the session to a log at the given path, overwriting it.
+    Record(PathBuf),
+    /// Replays a recorded session. The keyboard shortcuts and the [`PaintBrush`] are disabled
+    /// meanwhile.
+    ///
+    /// [`PaintBrush`]: crate::PaintBrush
+    Replay(ReplayLog),
+}
+
+/// Something that happened during a recorded session.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayEvent {
+    Step,
+    ShuffleColors(ShuffleColors),
+    Paused(Paused),
+    ReverseTime(ReverseTime),
+    Recenter(RecenterConfig),
+    AttractionRadius(AttractionRadius),
+    PeakFraction(PeakFraction),
+    Kernel(Kernel),
+    Friction(Friction),
+    MaxDelta(MaxDelta),
+    ColorAttractions(ColorAttractions),
+    ColorRadiusScale(ColorRadiusScale),
+    ForceComputation(ForceComputation),
+    Lifespan(Option<Lifespan>),
+    SettlePhase(Option<SettlePhase>),
+    Containment(Containment),
+    Anisotropy(Anisotropy),
+    QuorumSensing(Option<QuorumSensing>),
+    SmoothCutoff(SmoothCutoff),
+    ComputeBudget(ComputeBudget),
+    AdaptiveTimestep(Option<AdaptiveTimestep>),
+    Spawn(SpawnParticle),
+    Despawn(ParticleIndex),
+}
+
+/// A recorded session, see the [module documentation](self) for its format.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayLog {
+    pub seed: u64,
+    /// The events and the frames they happened in, in chronological order.
+    pub events: Vec<(u64, ReplayEvent)>,
+}
+
+#[derive(Debug)]
+pub enum ReplayLogError {
+    Io(io::Error),
+    /// Line `line`, counting from `1`, is malformed.
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ReplayLogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl Error for ReplayLogError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReplayLogError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl ReplayLog {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ParticleLifeError> {
+        Ok(std::fs::read_to_string(path)?.parse::<Self>()?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for ReplayLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "seed {}", self.seed)?;
+        for (frame, event) in &self.events {
+            writeln!(f, "{frame} {event}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ReplayLog {
+    type Err = ReplayLogError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut seed = None;
+        let mut events = Vec::new();
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            parse_line(line, &mut seed, &mut events).map_err(|message| ReplayLogError::Parse {
+                line: index + 1,
+                message,
+            })?;
+        }
+
+        let seed = seed.ok_or_else(|| ReplayLogError::Parse {
+            line: 1,
+            message: "missing `seed`".to_string(),
+        })?;
+        Ok(Self { seed, events })
+    }
+}
+
+fn parse_line(
+    line: &str,
+    seed: &mut Option<u64>,
+    events: &mut Vec<(u64, ReplayEvent)>,
+) -> Result<(), String> {
+    let mut tokens = Tokens(line.split_whitespace());
+    if seed.is_none() {
+        if tokens.next()? != "seed" {
+            return Err("expected `seed` first".to_string());
+        }
+        *seed = Some(tokens.parse()?);
+    } else {
+        let frame = tokens.parse()?;
+        if events.last().is_some_and(|&(last, _)| frame < last) {
+            return Err("frames are out of order".to_string());
+        }
+        events.push((frame, ReplayEvent::parse(&mut tokens)?));
+    }
+    tokens.finish()
+}
+
+/// The space-separated tokens of a line of a [`ReplayLog`], of a
+/// [`PartialConfig`](crate::PartialConfig) or of a text
+/// [`SimulationSnapshot`](crate::SimulationSnapshot).
+pub(crate) struct Tokens<'a>(pub(crate) SplitWhitespace<'a>);
+
+impl<'a> Tokens<'a> {
+    pub(crate) fn next(&mut self) -> Result<&'a str, String> {
+        self.0
+            .next()
+            .ok_or_else(|| "unexpected end of line".to_string())
+    }
+
+    pub(crate) fn parse<T: FromStr>(&mut self) -> Result<T, String>
+    where
+        T::Err: fmt::Display,
+    {
+        let token = self.next()?;
+        token
+            .parse()
+            .map_err(|error| format!("invalid value `{token}`: {error}"))
+    }
+
+    pub(crate) fn finish(mut self) -> Result<(), String> {
+        match self.0.next() {
+            Some(token) => Err(format!("unexpected `{token}`")),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn kernel(&mut self) -> Result<Kernel, String> {
+        match self.next()? {
+            "tent" => Ok(Kernel::Tent),
+            "smoothstep" => Ok(Kernel::Smoothstep),
+            "gaussian" => Ok(Kernel::Gaussian),
+            kernel => Err(format!("unknown kernel `{kernel}`")),
+        }
+    }
+
+    pub(crate) fn topology(&mut self) -> Result<Topology, String> {
+        match self.next()? {
+            "torus" => Ok(Topology::Torus),
+            "cylinder_x" => Ok(Topology::CylinderX),
+            "cylinder_y" => Ok(Topology::CylinderY),
+            "klein" => Ok(Topology::Klein),
+            topology => Err(format!("unknown topology `{topology}`")),
+        }
+    }
+
+    /// Parses the remaining tokens as colors in hexadecimal, with or without a leading `#`.
+    pub(crate) fn colors(&mut self) -> Result<Vec<Color>, String> {
+        self.0
+            .by_ref()
+            .map(|color| {
+                Color::hex(color.trim_start_matches('#'))
+                    .map_err(|error| format!("invalid color `{color}`: {error}"))
+            })
+            .collect()
+    }
+
+    /// Parses `none` as `None`, and anything else with `parse`.
+    fn optional<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, String>,
+    ) -> Result<Option<T>, String> {
+        if self.0.clone().next() == Some("none") {
+            self.0.next();
+            return Ok(None);
+        }
+        parse(self).map(Some)
+    }
+
+    /// Parses an attraction matrix, its rows being separated by `;` and their entries by `,`.
+    pub(crate) fn color_attractions(&mut self) -> Result<ColorAttractions, String> {
+        // An empty matrix has no token
+        let rows = match self.0.next() {
+            Some(rows) => {
+                rows.split(';')
+                    .map(|row| {
+                        row.split(',')
+                            .map(|entry| {
+                                entry.parse().map(Attraction).map_err(|error| {
+                                    format!("invalid attraction `{entry}`: {error}")
+                                })
+                            })
+                            .collect()
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+            None => Vec::new(),
+        };
+        Ok(ColorAttractions(rows))
+    }
+}
+
+impl ReplayEvent {
+    pub(crate) fn parse(tokens: &mut Tokens) -> Result<Self, String> {
+        let event = match tokens.next()? {
+            "step" => Self::Step,
+            "shuffle_colors" => Self::ShuffleColors(ShuffleColors {
+                preserve_counts: tokens.parse()?,
+            }),
+            "paused" => Self::Paused(Paused(tokens.parse()?)),
+            "reverse_time" => Self::ReverseTime(ReverseTime(tokens.parse()?)),
+            "recenter" => Self::Recenter(RecenterConfig {
+                enabled: tokens.parse()?,
+            }),
+            "attraction_radius" => Self::AttractionRadius(AttractionRadius {
+                rmin: tokens.parse()?,
+                rmax: tokens.parse()?,
+            }),
+            "peak_fraction" => Self::PeakFraction(PeakFraction(tokens.parse()?)),
+            "kernel" => Self::Kernel(tokens.kernel()?),
+            "friction" => Self::Friction(Friction(tokens.parse()?)),
+            "max_delta" => Self::MaxDelta(MaxDelta(tokens.parse()?)),
+            "color_attractions" => Self::ColorAttractions(tokens.color_attractions()?),
+            "color_radius_scale" => {
+                // An empty scale has no token
+                let scales = match tokens.0.next() {
+                    Some(scales) => scales
+                        .split(',')
+                        .map(|scale| {
+                            scale
+                                .parse()
+                                .map_err(|error| format!("invalid scale `{scale}`: {error}"))
+                        })
+                        .collect::<Result<_, _>>()?,
+                    None => Vec::new(),
+                };
+                Self::ColorRadiusScale(ColorRadiusScale(scales))
+            }
+            "force_computation" => Self::ForceComputation(match tokens.next()? {
+                "immediate" => ForceComputation::Immediate,
+                "background" => ForceComputation::Background,
+                "parallel" => ForceComputation::Parallel,
+                computation => return Err(format!("unknown force computation `{computation}`")),
+            }),
+            "lifespan" => Self::Lifespan(tokens.optional(|tokens| {
+                Ok(Lifespan {
+                    max_age: tokens.parse()?,
+                    fade_time: tokens.parse()?,
+                })
+            })?),
+            "settle_phase" => Self::SettlePhase(tokens.optional(|tokens| {
+                Ok(SettlePhase {
+                    duration: tokens.parse()?,
+                    initial_friction: tokens.parse()?,
+                })
+            })?),
+            "containment" => Self::Containment(Containment {
+                enabled: tokens.parse()?,
+                radius: tokens.parse()?,
+                strength: tokens.parse()?,
+            }),
+            "anisotropy" => Self::Anisotropy(Anisotropy {
+                x: tokens.parse()?,
+                y: tokens.parse()?,
+            }),
+            "quorum_sensing" => Self::QuorumSensing(tokens.optional(|tokens| {
+                Ok(QuorumSensing {
+                    threshold: tokens.parse()?,
+                    below: tokens.parse()?,
+                    above: tokens.parse()?,
+                })
+            })?),
+            "smooth_cutoff" => Self::SmoothCutoff(SmoothCutoff {
+                window: tokens.parse()?,
+            }),
+            "compute_budget" => Self::ComputeBudget(ComputeBudget {
+                max_pairs_per_frame: tokens.optional(Tokens::parse)?,
+            }),
+            "adaptive_timestep" => Self::AdaptiveTimestep(tokens.optional(|tokens| {
+                Ok(AdaptiveTimestep {
+                    min_dt: tokens.parse()?,
+                    max_dt: tokens.parse()?,
+                    max_displacement: tokens.parse()?,
+                })
+            })?),
+            "spawn" => Self::Spawn(SpawnParticle {
+                simulation: SimulationId(tokens.parse()?),
+                particle: Particle {
+                    position: Position(RealVec2::new(tokens.parse()?, tokens.parse()?)),
+                    velocity: Velocity(RealVec2::new(tokens.parse()?, tokens.parse()?)),
+                    color: ColorId(tokens.parse()?),
+                },
+            }),
+            "despawn" => Self::Despawn(ParticleIndex(tokens.parse()?)),
+            kind => return Err(format!("unknown event `{kind}`")),
+        };
+        Ok(event)
+    }
+}
+
+impl fmt::Display for ReplayEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Step => write!(f, "step"),
+            Self::ShuffleColors(shuffle) => write!(f, "shuffle_colors {}", shuffle.preserve_counts),
+            Self::Paused(paused) => write!(f, "paused {}", paused.0),
+            Self::ReverseTime(reverse_time) => write!(f, "reverse_time {}", reverse_time.0),
+            Self::Recenter(recenter) => write!(f, "recenter {}", recenter.enabled),
+            Self::AttractionRadius(radius) => {
+                write!(f, "attraction_radius {} {}", radius.rmin, radius.rmax)
+            }
+            Self::PeakFraction(peak_fraction) => write!(f, "peak_fraction {}", peak_fraction.0),
+            Self::Kernel(kernel) => {
+                let kernel = match kernel {
+                    Kernel::Tent => "tent",
+                    Kernel::Smoothstep => "smoothstep",
+                    Kernel::Gaussian => "gaussian",
+                };
+                write!(f, "kernel {kernel}")
+            }
+            Self::Friction(friction) => write!(f, "friction {}", friction.0),
+            Self::MaxDelta(max_delta) => write!(f, "max_delta {}", max_delta.0),
+            Self::ColorAttractions(attractions) => {
+                write!(f, "color_attractions")?;
+                for (i, row) in attractions.0.iter().enumerate() {
+                    write!(f, "{}", if i == 0 { " " } else { ";" })?;
+                    for (j, attraction) in row.iter().enumerate() {
+                        if j > 0 {
+                            write!(f, ",")?;
+                        }
+                        write!(f, "{}", attraction.0)?;
+                    }
+                }
+                Ok(())
+            }
+            Self::ColorRadiusScale(scale) => {
+                write!(f, "color_radius_scale")?;
+                for (i, scale) in scale.0.iter().enumerate() {
+                    write!(f, "{}{scale}", if i == 0 { " " } else { "," })?;
+                }
+                Ok(())
+            }
+            Self::ForceComputation(computation) => {
+                let computation = match computation {
+                    ForceComputation::Immediate => "immediate",
+                    ForceComputation::Background => "background",
+                    ForceComputation::Parallel => "parallel",
+                };
+                write!(f, "force_computation {computation}")
+            }
+            Self::Lifespan(None) => write!(f, "lifespan none"),
+            Self::Lifespan(Some(lifespan)) => {
+                write!(f, "lifespan {} {}", lifespan.max_age, lifespan.fade_time)
+            }
+            Self::SettlePhase(None) => write!(f, "settle_phase none"),
+            Self::SettlePhase(Some(settle_phase)) => write!(
+                f,
+                "settle_phase {} {}",
+                settle_phase.duration, settle_phase.initial_friction
+            ),
+            Self::Containment(containment) => write!(
+                f,
+                "containment {} {} {}",
+                containment.enabled, containment.radius, containment.strength
+            ),
+            Self::Anisotropy(anisotropy) => {
+                write!(f, "anisotropy {} {}", anisotropy.x, anisotropy.y)
+            }
+            Self::QuorumSensing(None) => write!(f, "quorum_sensing none"),
+            Self::QuorumSensing(Some(quorum)) => write!(
+                f,
+                "quorum_sensing {} {} {}",
+                quorum.threshold, quorum.below, quorum.above
+            ),
+            Self::SmoothCutoff(smooth_cutoff) => {
+                write!(f, "smooth_cutoff {}", smooth_cutoff.window)
+            }
+            Self::ComputeBudget(budget) => match budget.max_pairs_per_frame {
+                Some(max_pairs) => write!(f, "compute_budget {max_pairs}"),
+                None => write!(f, "compute_budget none"),
+            },
+            Self::AdaptiveTimestep(None) => write!(f, "adaptive_timestep none"),
+            Self::AdaptiveTimestep(Some(timestep)) => write!(
+                f,
+                "adaptive_timestep {} {} {}",
+                timestep.min_dt, timestep.max_dt, timestep.max_displacement
+            ),
+            Self::Spawn(SpawnParticle {
+                particle,
+                simulation,
+            }) => write!(
+                f,
+                "spawn {} {} {} {} {} {}",
+                simulation.0,
+                particle.position.0.x,
+                particle.position.0.y,
+                particle.velocity.0.x,
+                particle.velocity.0.y,
+                particle.color.0,
+            ),
+            Self::Despawn(index) => write!(f, "despawn {}", index.0),
+        }
+    }
+}
+
+/// Makes [`update_simulation_time`] advance by a fixed time step of [`MaxDelta`] every frame.
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub(crate) struct FixedTimeStep;
+
+/// The frame the session is at, counting from `0`.
+#[derive(Debug, Clone, Copy, Default, Resource)]
+struct ReplayFrame(u64);
+
+#[derive(Resource)]
+struct Recorder {
+    /// `None` once writing failed.
+    writer: Option<BufWriter<File>>,
+}
+
+/// The events of the log left to replay.
+#[derive(Debug, Clone, Resource)]
+pub(crate) struct Replayer {
+    events: std::vec::IntoIter<(u64, ReplayEvent)>,
+}
+
+/// The recorded simulation parameters.
+#[derive(SystemParam)]
+struct Parameters<'w, 's> {
+    motion: MotionParameters<'w, 's>,
+    forces: ForceParameters<'w, 's>,
+}
+
+/// The parameters of how particles move, split from [`Parameters`] to keep below the limit of
+/// fields of a [`SystemParam`].
+#[derive(SystemParam)]
+struct MotionParameters<'w, 's> {
+    paused: ResMut<'w, Paused>,
+    reverse_time: ResMut<'w, ReverseTime>,
+    recenter: ResMut<'w, RecenterConfig>,
+    friction: ResMut<'w, Friction>,
+    max_delta: ResMut<'w, MaxDelta>,
+    settle_phase: OptionalParameter<'w, 's, SettlePhase>,
+    lifespan: OptionalParameter<'w, 's, Lifespan>,
+    adaptive_timestep: OptionalParameter<'w, 's, AdaptiveTimestep>,
+}
+
+/// The parameters of the forces between particles.
+#[derive(SystemParam)]
+struct ForceParameters<'w, 's> {
+    attraction_radius: ResMut<'w, AttractionRadius>,
+    color_radius_scale: ResMut<'w, ColorRadiusScale>,
+    peak_fraction: ResMut<'w, PeakFraction>,
+    kernel: ResMut<'w, Kernel>,
+    color_attractions: ResMut<'w, ColorAttractions>,
+    force_computation: ResMut<'w, ForceComputation>,
+    containment: ResMut<'w, Containment>,
+    anisotropy: ResMut<'w, Anisotropy>,
+    quorum_sensing: OptionalParameter<'w, 's, QuorumSensing>,
+    smooth_cutoff: ResMut<'w, SmoothCutoff>,
+    compute_budget: ResMut<'w, ComputeBudget>,
+}
+
+/// A parameter that is disabled by removing its resource.
+#[derive(SystemParam)]
+struct OptionalParameter<'w, 's, T: Resource> {
+    resource: Option<Res<'w, T>>,
+    /// Whether the resource existed when the system last ran, `None` on its first run.
+    existed: Local<'s, Option<bool>>,
+    commands: Commands<'w, 's>,
+}
+
+impl<T: Resource + Clone> OptionalParameter<'_, '_, T> {
+    /// The value of the parameter if it was inserted, changed or removed since the system last
+    /// ran, or on its first run.
+    fn change(&mut self) -> Option<Option<T>> {
+        let existed = self.existed.replace(self.resource.is_some());
+        let changed = match &self.resource {
+            Some(resource) => resource.is_changed() || existed != Some(true),
+            None => existed != Some(false),
+        };
+        changed.then(|| self.resource.as_deref().cloned())
+    }
+
+    /// Inserts or removes the resource, at the end of the stage.
+    fn set(&mut self, value: Option<T>) {
+        match value {
+            Some(value) => self.commands.insert_resource(value),
+            None => self.commands.remove_resource::<T>(),
+        }
+    }
+}
+
+impl Parameters<'_, '_> {
+    /// The parameters that changed since the system last ran.
+    fn changes(&mut self) -> Vec<ReplayEvent> {
+        let Self { motion, forces } = self;
+        let mut changes = Vec::new();
+        if motion.paused.is_changed() {
+            changes.push(ReplayEvent::Paused(*motion.paused));
+        }
+        if motion.reverse_time.is_changed() {
+            changes.push(ReplayEvent::ReverseTime(*motion.reverse_time));
+        }
+        if motion.recenter.is_changed() {
+            changes.push(ReplayEvent::Recenter(*motion.recenter));
+        }
+        if motion.friction.is_changed() {
+            changes.push(ReplayEvent::Friction(*motion.friction));
+        }
+        if motion.max_delta.is_changed() {
+            changes.push(ReplayEvent::MaxDelta(*motion.max_delta));
+        }
+        if let Some(settle_phase) = motion.settle_phase.change() {
+            changes.push(ReplayEvent::SettlePhase(settle_phase));
+        }
+        if let Some(lifespan) = motion.lifespan.change() {
+            changes.push(ReplayEvent::Lifespan(lifespan));
+        }
+        if let Some(adaptive_timestep) = motion.adaptive_timestep.change() {
+            changes.push(ReplayEvent::AdaptiveTimestep(adaptive_timestep));
+        }
+        if forces.attraction_radius.is_changed() {
+            changes.push(ReplayEvent::AttractionRadius(*forces.attraction_radius));
+        }
+        if forces.color_radius_scale.is_changed() {
+            changes.push(ReplayEvent::ColorRadiusScale(
+                forces.color_radius_scale.clone(),
+            ));
+        }
+        if forces.peak_fraction.is_changed() {
+            changes.push(ReplayEvent::PeakFraction(*forces.peak_fraction));
+        }
+        if forces.kernel.is_changed() {
+            changes.push(ReplayEvent::Kernel(*forces.kernel));
+        }
+        if forces.color_attractions.is_changed() {
+            changes.push(ReplayEvent::ColorAttractions(
+                forces.color_attractions.clone(),
+            ));
+        }
+        if forces.force_computation.is_changed() {
+            changes.push(ReplayEvent::ForceComputation(*forces.force_computation));
+        }
+        if forces.containment.is_changed() {
+            changes.push(ReplayEvent::Containment(*forces.containment));
+        }
+        if forces.anisotropy.is_changed() {
+            changes.push(ReplayEvent::Anisotropy(*forces.anisotropy));
+        }
+        if let Some(quorum_sensing) = forces.quorum_sensing.change() {
+            changes.push(ReplayEvent::QuorumSensing(quorum_sensing));
+        }
+        if forces.smooth_cutoff.is_changed() {
+            changes.push(ReplayEvent::SmoothCutoff(*forces.smooth_cutoff));
+        }
+        if forces.compute_budget.is_changed() {
+            changes.push(ReplayEvent::ComputeBudget(*forces.compute_budget));
+        }
+        changes
+    }
+
+    fn apply(&mut self, event: ReplayEvent) {
+        let Self { motion, forces } = self;
+        match event {
+            ReplayEvent::Paused(paused) => *motion.paused = paused,
+            ReplayEvent::ReverseTime(reverse_time) => *motion.reverse_time = reverse_time,
+            ReplayEvent::Recenter(recenter) => *motion.recenter = recenter,
+            ReplayEvent::Friction(friction) => *motion.friction = friction,
+            ReplayEvent::MaxDelta(max_delta) => *motion.max_delta = max_delta,
+            ReplayEvent::SettlePhase(settle_phase) => motion.settle_phase.set(settle_phase),
+            ReplayEvent::Lifespan(lifespan) => motion.lifespan.set(lifespan),
+            ReplayEvent::AdaptiveTimestep(timestep) => motion.adaptive_timestep.set(timestep),
+            ReplayEvent::AttractionRadius(radius) => *forces.attraction_radius = radius,
+            ReplayEvent::ColorRadiusScale(scale) => *forces.color_radius_scale = scale,
+            ReplayEvent::PeakFraction(peak_fraction) => *forces.peak_fraction = peak_fraction,
+            ReplayEvent::Kernel(kernel) => *forces.kernel = kernel,
+            ReplayEvent::ColorAttractions(attractions) => *forces.color_attractions = attractions,
+            ReplayEvent::ForceComputation(computation) => *forces.force_computation = computation,
+            ReplayEvent::Containment(containment) => *forces.containment = containment,
+            ReplayEvent::Anisotropy(anisotropy) => *forces.anisotropy = anisotropy,
+            ReplayEvent::QuorumSensing(quorum_sensing) => forces.quorum_sensing.set(quorum_sensing),
+            ReplayEvent::SmoothCutoff(smooth_cutoff) => *forces.smooth_cutoff = smooth_cutoff,
+            ReplayEvent::ComputeBudget(budget) => *forces.compute_budget = budget,
+            ReplayEvent::Step
+            | ReplayEvent::ShuffleColors(_)
+            | ReplayEvent::Spawn(_)
+            | ReplayEvent::Despawn(_) => {}
+        }
+    }
+}
+
+/// Sets up `replay_mode`, for a simulation seeded with `seed`.
+pub(crate) fn build(app: &mut App, replay_mode: &ReplayMode, seed: u64) {
+    match replay_mode {
+        ReplayMode::Off => return,
+        ReplayMode::Record(path) => {
+            let writer = File::create(path)
+                .and_then(|file| {
+                    let mut writer = BufWriter::new(file);
+                    writeln!(writer, "seed {seed}")?;
+                    Ok(writer)
+                })
+                .unwrap_or_else(|error| panic!("failed to create {}: {error}", path.display()));
+            app.insert_resource(Recorder {
+                writer: Some(writer),
+            })
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                record
+                    .after(handle_shortcuts)
+                    .after(reset_particles)
+                    .before(update_simulation_time)
+                    .before(apply_structural_changes),
+            );
+        }
+        ReplayMode::Replay(log) => {
+            app.insert_resource(Replayer {
+                events: log.events.clone().into_iter(),
+            })
+            // The resources inserted and removed at the end of the stage are then in place for the
+            // time step
+            .add_system_to_stage(CoreStage::First, replay);
+        }
+    }
+
+    app.insert_resource(FixedTimeStep)
+        .init_resource::<ReplayFrame>()
+        .add_system_to_stage(CoreStage::Last, advance_frame);
+}
+
+fn advance_frame(mut frame: ResMut<ReplayFrame>) {
+    frame.0 += 1;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record(
+    frame: Res<ReplayFrame>,
+    mut parameters: Parameters,
+    mut recorder: ResMut<Recorder>,
+    mut steps: EventReader<StepSimulation>,
+    mut shuffles: EventReader<ShuffleColors>,
+    mut spawns: EventReader<SpawnParticle>,
+    mut despawns: EventReader<DespawnParticle>,
+    indices: Query<&ParticleIndex>,
+) {
+    let mut events = parameters.changes();
+    events.extend(steps.iter().map(|_| ReplayEvent::Step));
+    events.extend(
+        shuffles
+            .iter()
+            .map(|&shuffle| ReplayEvent::ShuffleColors(shuffle)),
+    );
+    events.extend(spawns.iter().map(|&spawn| ReplayEvent::Spawn(spawn)));
+    // Particles already despawned are ignored by the despawn too
+    events.extend(
+        despawns
+            .iter()
+            .filter_map(|&DespawnParticle(entity)| indices.get(entity).ok())
+            .map(|&index| ReplayEvent::Despawn(index)),
+    );
+    if events.is_empty() {
+        return;
+    }
+
+    let Some(writer) = &mut recorder.writer else {
+        return;
+    };
+    let result = events
+        .iter()
+        .try_for_each(|event| writeln!(writer, "{} {event}", frame.0))
+        .and_then(|()| writer.flush());
+    if let Err(error) = result {
+        error!(target: LOG_TARGET, event = "recording_failed", %error);
+        recorder.writer = None;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn replay(
+    frame: Res<ReplayFrame>,
+    mut parameters: Parameters,
+    mut replayer: ResMut<Replayer>,
+    mut steps: EventWriter<StepSimulation>,
+    mut shuffles: EventWriter<ShuffleColors>,
+    mut spawns: EventWriter<SpawnParticle>,
+    mut despawns: EventWriter<DespawnParticle>,
+    particles: Query<(Entity, &ParticleIndex)>,
+) {
+    while let Some(&(event_frame, _)) = replayer.events.as_slice().first() {
+        if event_frame > frame.0 {
+            break;
+        }
+        let Some((_, event)) = replayer.events.next() else {
+            break;
+        };
+        match event {
+            ReplayEvent::Step => steps.send(StepSimulation),
+            ReplayEvent::ShuffleColors(shuffle) => shuffles.send(shuffle),
+            ReplayEvent::Spawn(spawn) => spawns.send(spawn),
+            ReplayEvent::Despawn(index) => {
+                if let Some((entity, _)) = particles.iter().find(|&(_, &other)| other == index) {
+                    despawns.send(DespawnParticle(entity));
+                }
+            }
+            event => parameters.apply(event),
+        }
+        if replayer.events.as_slice().is_empty() {
+            info!(target: LOG_TARGET, event = "replay_finished", frame = frame.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clock::{headless_app, test_plugin},
+        precision::real_vec2,
+    };
+
+    fn particle(x: f32, y: f32, color: usize) -> Particle {
+        Particle {
+            position: Position(real_vec2(Vec2::new(x, y))),
+            velocity: Velocity(RealVec2::ZERO),
+            color: ColorId(color),
+        }
+    }
+
+    fn plugin(replay: ReplayMode) -> crate::ParticleLifePlugin {
+        let initial_particles = (0..40)
+            .map(|i| {
+                let i = i as f32;
+                particle(
+                    (i * 0.37).sin() * 0.5,
+                    (i * 0.61).cos() * 0.5,
+                    i as usize % 2,
+                )
+            })
+            .collect();
+        crate::ParticleLifePlugin {
+            color_attractions: ColorAttractions(vec![
+                vec![Attraction(0.5), Attraction(-0.3)],
+                vec![Attraction(0.2), Attraction(0.4)],
+            ]),
+            replay,
+            ..test_plugin(initial_particles)
+        }
+    }
+
+    fn positions(app: &mut App) -> Vec<(ParticleIndex, Position)> {
+        let mut positions: Vec<_> = app
+            .world
+            .query::<(&ParticleIndex, &Position)>()
+            .iter(&app.world)
+            .map(|(&index, &position)| (index, position))
+            .collect();
+        positions.sort_by_key(|&(index, _)| index);
+        positions
+    }
+
+    fn update(app: &mut App, frames: usize) {
+        for _ in 0..frames {
+            app.update();
+        }
+    }
+
+    #[test]
+    fn replays_reproduce_the_recorded_positions() {
+        let path = std::env::temp_dir().join(format!(
+            "particle_life_replay_round_trip_{}.txt",
+            std::process::id()
+        ));
+
+        let mut app = headless_app(plugin(ReplayMode::Record(path.clone())));
+        update(&mut app, 5);
+        app.insert_resource(Friction(2.0))
+            .insert_resource(Lifespan {
+                max_age: 0.2,
+                fade_time: 0.0,
+            })
+            .insert_resource(AdaptiveTimestep {
+                min_dt: 0.001,
+                max_dt: 0.01,
+                max_displacement: 0.001,
+            })
+            .insert_resource(Containment {
+                radius: 0.2,
+                strength: 2.0,
+                enabled: true,
+            });
+        update(&mut app, 5);
+        app.insert_resource(ColorRadiusScale(vec![1.0, 0.5]))
+            .insert_resource(ForceComputation::Parallel)
+            .insert_resource(Anisotropy { x: 1.5, y: 1.0 })
+            .insert_resource(SmoothCutoff { window: 0.3 })
+            .insert_resource(QuorumSensing {
+                threshold: 3,
+                below: 1.0,
+                above: -0.5,
+            })
+            .insert_resource(SettlePhase {
+                duration: 0.5,
+                initial_friction: 10.0,
+            });
+        app.world.send_event(SpawnParticle {
+            particle: particle(0.1, -0.1, 1),
+            simulation: SimulationId::MAIN,
+        });
+        update(&mut app, 3);
+        let entity = app
+            .world
+            .query::<(Entity, &ParticleIndex)>()
+            .iter(&app.world)
+            .find(|&(_, &index)| index == ParticleIndex(3))
+            .map(|(entity, _)| entity)
+            .unwrap();
+        app.world.send_event(DespawnParticle(entity));
+        app.world.remove_resource::<Lifespan>();
+        app.insert_resource(ComputeBudget {
+            max_pairs_per_frame: Some(200),
+        });
+        update(&mut app, 5);
+        let recorded = positions(&mut app);
+        drop(app);
+
+        let log = ReplayLog::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        for kind in [
+            "lifespan none",
+            "color_radius_scale 1,0.5",
+            "spawn 0",
+            "despawn 3",
+        ] {
+            assert!(
+                log.events
+                    .iter()
+                    .any(|(_, event)| event.to_string().starts_with(kind)),
+                "no `{kind}` in {log}"
+            );
+        }
+        assert_eq!(log.to_string().parse::<ReplayLog>().unwrap(), log);
+
+        let mut app = headless_app(plugin(ReplayMode::Replay(log)));
+        update(&mut app, 18);
+        assert_eq!(positions(&mut app), recorded);
+    }
+}
@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+
+use crate::{
+    apply_structural_changes, keymap::handle_shortcuts, logging::LOG_TARGET, DespawnParticle,
+    ParticleIndex, SpawnParticle,
+};
+
+/// Send this event to start the simulation over: all the particles are despawned and the initial
+/// particles of every simulation are spawned again, with [`DespawnParticle`] and [`SpawnParticle`]
+/// events.
+///
+/// The parameters of the simulation are left as they are. The new particles are numbered after
+/// all the particles spawned before them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResetParticles;
+
+/// The particles spawned when the app starts.
+#[derive(Debug, Clone, Default, Resource)]
+pub(crate) struct InitialParticles(Vec<SpawnParticle>);
+
+pub(crate) fn build(app: &mut App, initial_particles: Vec<SpawnParticle>) {
+    app.insert_resource(InitialParticles(initial_particles))
+        .add_event::<ResetParticles>()
+        .add_system_to_stage(
+            CoreStage::PreUpdate,
+            reset_particles
+                .after(handle_shortcuts)
+                .before(apply_structural_changes),
+        );
+}
+
+pub(crate) fn reset_particles(
+    mut resets: EventReader<ResetParticles>,
+    initial_particles: Res<InitialParticles>,
+    mut spawns: EventWriter<SpawnParticle>,
+    mut despawns: EventWriter<DespawnParticle>,
+    query: Query<Entity, With<ParticleIndex>>,
+) {
+    // Several resets in a frame amount to one
+    if resets.iter().count() == 0 {
+        return;
+    }
+    info!(target: LOG_TARGET, event = "particles_reset");
+    despawns.send_batch(query.iter().map(DespawnParticle));
+    spawns.send_batch(initial_particles.0.iter().copied());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        advance_steps,
+        clock::{headless_app, test_plugin},
+        precision::real_vec2,
+        ColorId, Particle, Paused, Position, Velocity,
+    };
+
+    #[test]
+    fn reset_respawns_the_initial_particles() {
+        let initial_particles: Vec<_> = (0..4)
+            .map(|i| Particle {
+                position: Position(real_vec2(Vec2::new(0.1 * i as f32, 0.0))),
+                velocity: Velocity(real_vec2(Vec2::new(0.0, 0.5))),
+                color: ColorId(i % 2),
+            })
+            .collect();
+        let mut app = headless_app(test_plugin(initial_particles.clone()));
+        advance_steps(&mut app, 0.01, 5);
+
+        // Keeps the respawned particles where they start
+        app.insert_resource(Paused(true));
+        app.world.send_event(ResetParticles);
+        advance_steps(&mut app, 0.01, 1);
+
+        let mut query = app
+            .world
+            .query::<(&Position, &Velocity, &ColorId, &ParticleIndex)>();
+        let mut particles: Vec<_> = query.iter(&app.world).collect();
+        particles.sort_by_key(|&(.., &index)| index);
+        let particles: Vec<_> = particles
+            .into_iter()
+            .map(|(&position, &velocity, &color, _)| Particle {
+                position,
+                velocity,
+                color,
+            })
+            .collect();
+        assert_eq!(particles, initial_particles);
+    }
+}
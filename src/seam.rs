@@ -0,0 +1,119 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    lifespan::fade_particles, precision::single_vec2, Age, ColorHandles, ColorId, Lifespan,
+    ParticleColors, ParticleLifeSystem, Position, WorldBounds,
+};
+
+/// Number of distinct tints a particle goes through as it nears the seam, each with its own
+/// material.
+const SEAM_LEVELS: u8 = 16;
+
+/// Tints the particles near the edges of the world toward `color`, to show that they wrap around
+/// to the opposite edge. A teaching aid only: the physics is unaffected.
+///
+/// Particles farther than `margin` from every edge keep their color, and the tint grows linearly
+/// up to `color` itself right at an edge. Particles fading out at the end of their [`Lifespan`]
+/// aren't tinted.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct SeamHighlight {
+    pub enabled: bool,
+    pub color: Color,
+    pub margin: f32,
+}
+
+impl Default for SeamHighlight {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: Color::WHITE,
+            margin: 0.05,
+        }
+    }
+}
+
+/// Materials for tinted particles, by color and tint level, created as needed.
+#[derive(Debug, Clone, Default, Resource)]
+struct SeamMaterials(HashMap<(usize, u8), Handle<ColorMaterial>>);
+
+pub(crate) fn build(app: &mut App, seam_highlight: SeamHighlight) {
+    app.insert_resource(seam_highlight)
+        .init_resource::<SeamMaterials>()
+        .add_system(
+            highlight_seam
+                .after(ParticleLifeSystem::UpdateMaterial)
+                .after(fade_particles),
+        );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn highlight_seam(
+    seam_highlight: Res<SeamHighlight>,
+    bounds: Res<WorldBounds>,
+    lifespan: Option<Res<Lifespan>>,
+    colors: Res<ParticleColors>,
+    handles: Res<ColorHandles>,
+    mut seam_materials: ResMut<SeamMaterials>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut query: Query<(
+        &mut Handle<ColorMaterial>,
+        &Position,
+        &ColorId,
+        Option<&Age>,
+    )>,
+) {
+    if !seam_highlight.enabled && !seam_highlight.is_changed() {
+        return;
+    }
+    if seam_highlight.is_changed() || colors.is_changed() {
+        // The tint color or the colors of the particles may have changed
+        seam_materials.0.clear();
+    }
+
+    for (mut material, position, color, age) in &mut query {
+        let fading = match (&lifespan, age) {
+            (Some(lifespan), Some(age)) => age.0 > lifespan.max_age - lifespan.fade_time,
+            _ => false,
+        };
+        if fading {
+            continue;
+        }
+
+        let level = if seam_highlight.enabled {
+            let position = single_vec2(position.0);
+            let to_edge = (position - bounds.min)
+                .min(bounds.max - position)
+                .min_element();
+            let proximity = 1.0 - to_edge / seam_highlight.margin;
+            (proximity.clamp(0.0, 1.0) * SEAM_LEVELS as f32).round() as u8
+        } else {
+            // Restore the colors of all particles once disabled
+            0
+        };
+
+        let new_material = if level == 0 {
+            handles.0[color.0].clone()
+        } else {
+            seam_materials
+                .0
+                .entry((color.0, level))
+                .or_insert_with(|| {
+                    let t = level as f32 / SEAM_LEVELS as f32;
+                    let [r, g, b, a] = colors.0[color.0].as_rgba_f32();
+                    let [tint_r, tint_g, tint_b, tint_a] = seam_highlight.color.as_rgba_f32();
+                    let tinted = Color::rgba(
+                        r + t * (tint_r - r),
+                        g + t * (tint_g - g),
+                        b + t * (tint_b - b),
+                        a + t * (tint_a - a),
+                    );
+                    materials.add(ColorMaterial::from(tinted))
+                })
+                .clone()
+        };
+
+        if *material != new_material {
+            *material = new_material;
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+
+use std::f32::consts::TAU;
+
+use crate::{
+    camera::CursorPosition,
+    lines::Lines,
+    perf::add_overlay_font,
+    precision::{real_vec2, single_vec2},
+    toroidal_dist, ColorId, PaintBrush, ParticleIndex, ParticleNeighbors, Position, SimulationId,
+    Velocity, WorldBounds,
+};
+
+/// The particle selected by clicking it, if any.
+///
+/// Clicking in the main simulation selects the closest particle within reach of the cursor, or
+/// clears the selection when there is none, unless the [`PaintBrush`] is enabled. The selected
+/// particle is circled and its state is shown in the bottom-left corner of the window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub struct Selection(pub Option<Entity>);
+
+/// How far from the cursor a particle can be selected, in world units.
+const PICK_RADIUS: f32 = 0.03;
+
+/// The radius of the circle around the selected particle, in world units.
+const HIGHLIGHT_RADIUS: f32 = 0.025;
+
+const HIGHLIGHT_SEGMENTS: usize = 24;
+
+const FONT_SIZE: f32 = 16.0;
+
+/// Marks the text showing the state of the selected particle.
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct SelectionText;
+
+pub(crate) fn build(app: &mut App) {
+    app.init_resource::<Selection>()
+        .add_startup_system(setup_selection_text)
+        .add_system(select)
+        .add_system(highlight_selection.after(select))
+        .add_system(update_selection_text.after(select));
+}
+
+fn select(
+    buttons: Option<Res<Input<MouseButton>>>,
+    paint_brush: Res<PaintBrush>,
+    cursor: CursorPosition,
+    neighbors: Res<ParticleNeighbors>,
+    bounds: Res<WorldBounds>,
+    mut selection: ResMut<Selection>,
+    query: Query<(&Position, &SimulationId)>,
+) {
+    // Forget particles despawned since they were selected
+    if let Some(entity) = selection.0 {
+        if !query.contains(entity) {
+            selection.0 = None;
+        }
+    }
+
+    let Some(buttons) = buttons else {
+        return;
+    };
+    if paint_brush.enabled || !buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(cursor) = cursor.world_position() else {
+        return;
+    };
+
+    let cursor = real_vec2(cursor);
+    let selected = neighbors
+        .neighbors_within(single_vec2(cursor), PICK_RADIUS)
+        .into_iter()
+        .filter_map(|entity| {
+            let (position, &simulation) = query.get(entity).ok()?;
+            (simulation == SimulationId::MAIN)
+                .then(|| (toroidal_dist(cursor, position.0, &bounds), entity))
+        })
+        .min_by(|(distance, _), (other, _)| distance.total_cmp(other))
+        .map(|(_, entity)| entity);
+    if selection.0 != selected {
+        selection.0 = selected;
+    }
+}
+
+fn highlight_selection(
+    selection: Res<Selection>,
+    mut lines: ResMut<Lines>,
+    query: Query<&Position>,
+) {
+    let Some(position) = selection.0.and_then(|entity| query.get(entity).ok()) else {
+        return;
+    };
+    let center = single_vec2(position.0);
+    let point = |i: usize| {
+        center + HIGHLIGHT_RADIUS * Vec2::from_angle(i as f32 * TAU / HIGHLIGHT_SEGMENTS as f32)
+    };
+    for i in 0..HIGHLIGHT_SEGMENTS {
+        lines.line(point(i), point(i + 1), Color::WHITE);
+    }
+}
+
+fn setup_selection_text(mut commands: Commands, mut fonts: ResMut<Assets<Font>>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: add_overlay_font(&mut fonts),
+                font_size: FONT_SIZE,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(8.0),
+                bottom: Val::Px(8.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+        SelectionText,
+    ));
+}
+
+fn update_selection_text(
+    selection: Res<Selection>,
+    particles: Query<(&ParticleIndex, &ColorId, &Position, &Velocity)>,
+    mut query: Query<(&mut Text, &mut Visibility), With<SelectionText>>,
+) {
+    let particle = selection
+        .0
+        .and_then(|entity| Some((entity, particles.get(entity).ok()?)));
+    for (mut text, mut visibility) in &mut query {
+        visibility.is_visible = particle.is_some();
+        let Some((entity, (index, color, position, velocity))) = particle else {
+            continue;
+        };
+
+        let (position, velocity) = (single_vec2(position.0), single_vec2(velocity.0));
+        text.sections[0].value = format!(
+            "entity    {entity:?}\n\
+             index     {}\n\
+             color     {}\n\
+             position  {:>8.4} {:>8.4}\n\
+             velocity  {:>8.4} {:>8.4}",
+            index.0, color.0, position.x, position.y, velocity.x, velocity.y,
+        );
+    }
+}
@@ -0,0 +1,18 @@
+// The fullscreen pass of the trails: copies the trail texture of the previous frame, darkened by
+// `decay`.
+
+@group(1) @binding(0)
+var<uniform> decay: f32;
+@group(1) @binding(1)
+var previous: texture_2d<f32>;
+@group(1) @binding(2)
+var previous_sampler: sampler;
+
+struct FragmentInput {
+    #import bevy_sprite::mesh2d_vertex_output
+};
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    return textureSample(previous, previous_sampler, in.uv) * decay;
+}
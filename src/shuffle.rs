@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+
+use std::collections::BTreeMap;
+
+use crate::{
+    lifespan::age_particles, logging::LOG_TARGET, update_position, ColorId, ParticleColors,
+    ParticleLifeSystem, SimRng, SimulationId,
+};
+
+/// Send this event to randomly reassign the colors of all particles, keeping their positions, to
+/// see how the same layout evolves with a reshuffled palette. The shuffle draws from [`SimRng`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShuffleColors {
+    /// Whether to keep the number of particles of each color in each simulation, by permuting the
+    /// existing colors within each simulation. Otherwise, each particle gets a uniformly random
+    /// color.
+    pub preserve_counts: bool,
+}
+
+pub(crate) fn build(app: &mut App) {
+    app.add_event::<ShuffleColors>().add_system(
+        shuffle_colors
+            .after(age_particles)
+            .before(update_position)
+            .before(ParticleLifeSystem::UpdateMaterial),
+    );
+}
+
+fn shuffle_colors(
+    mut events: EventReader<ShuffleColors>,
+    colors: Res<ParticleColors>,
+    mut rng: ResMut<SimRng>,
+    mut query: Query<(&mut ColorId, &SimulationId)>,
+) {
+    for &ShuffleColors { preserve_counts } in events.iter() {
+        info!(target: LOG_TARGET, event = "colors_shuffled", preserve_counts);
+        let rng = &mut rng.0;
+
+        if preserve_counts {
+            // Shuffled in a fixed order of the simulations, to draw from the RNG reproducibly
+            let mut color_ids = BTreeMap::<SimulationId, Vec<ColorId>>::new();
+            for (&color, &simulation) in &query {
+                color_ids.entry(simulation).or_default().push(color);
+            }
+            for simulation_colors in color_ids.values_mut() {
+                simulation_colors.shuffle(rng);
+            }
+            for (mut color, simulation) in &mut query {
+                let simulation_colors = color_ids.get_mut(simulation).expect("collected above");
+                *color = simulation_colors.pop().expect("one color per particle");
+            }
+        } else {
+            for (mut color, _) in &mut query {
+                color.0 = rng.gen_range(0..colors.0.len());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        advance_steps,
+        clock::{headless_app, test_plugin},
+        ExtraSimulation, Particle, ParticleLifePlugin, Position, Velocity,
+    };
+
+    fn particles(colors: &[usize]) -> Vec<Particle> {
+        colors
+            .iter()
+            .map(|&color| Particle {
+                position: Position::default(),
+                velocity: Velocity::default(),
+                color: ColorId(color),
+            })
+            .collect()
+    }
+
+    fn color_counts(app: &mut App) -> BTreeMap<(SimulationId, usize), usize> {
+        let mut counts = BTreeMap::new();
+        let mut query = app.world.query::<(&ColorId, &SimulationId)>();
+        for (color, &simulation) in query.iter(&app.world) {
+            *counts.entry((simulation, color.0)).or_default() += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn preserve_counts_shuffles_within_each_simulation() {
+        let mut app = headless_app(ParticleLifePlugin {
+            extra_simulations: vec![ExtraSimulation {
+                initial_particles: particles(&[1; 20]),
+                ..Default::default()
+            }],
+            ..test_plugin(particles(&[0; 20]))
+        });
+        advance_steps(&mut app, 0.01, 1);
+        let counts = color_counts(&mut app);
+
+        app.world.send_event(ShuffleColors {
+            preserve_counts: true,
+        });
+        advance_steps(&mut app, 0.01, 1);
+
+        assert_eq!(color_counts(&mut app), counts);
+    }
+}
@@ -0,0 +1,70 @@
+//! Several independent simulations running side by side, for comparing parameters.
+//!
+//! Particles belong to the simulation given by their [`SimulationId`] and only interact with
+//! particles of the same simulation. All simulations share the same colors and world, but each
+//! may override the attraction settings of the main simulation, and is displayed in its own
+//! viewport, the window being split into vertical strips from left to right.
+
+use bevy::{prelude::*, render::view::RenderLayers, utils::HashMap};
+
+use crate::{AttractionRadius, ColorAttractions, ColorId, Particle};
+
+/// The simulation a particle belongs to. Particles spawned without one belong to
+/// [`SimulationId::MAIN`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
+pub struct SimulationId(pub usize);
+
+impl SimulationId {
+    /// The simulation of the [initial particles] of the plugin, configured by the global
+    /// resources.
+    ///
+    /// [initial particles]: crate::ParticleLifePlugin::initial_particles
+    pub const MAIN: Self = Self(0);
+}
+
+/// Settings of a simulation overriding the global resources. `None` fields fall back to the
+/// settings of the main simulation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimulationSettings {
+    pub color_attractions: Option<ColorAttractions>,
+    pub attraction_radius: Option<AttractionRadius>,
+}
+
+/// A simulation running alongside the main one, see
+/// [`ParticleLifePlugin::extra_simulations`](crate::ParticleLifePlugin::extra_simulations).
+#[derive(Debug, Clone, Default)]
+pub struct ExtraSimulation {
+    pub initial_particles: Vec<Particle>,
+    pub settings: SimulationSettings,
+}
+
+/// The settings of each simulation besides the main one. Simulations missing from the map use the
+/// settings of the main simulation.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct SimulationOverrides(pub HashMap<SimulationId, SimulationSettings>);
+
+pub(crate) fn build(app: &mut App, extra_simulations: &[ExtraSimulation]) {
+    // Each simulation is rendered on its own layer, and the last layer is kept for the trails
+    assert!(
+        extra_simulations.len() < RenderLayers::TOTAL_LAYERS - 1,
+        "at most {} extra simulations are supported",
+        RenderLayers::TOTAL_LAYERS - 2
+    );
+
+    let overrides = extra_simulations
+        .iter()
+        .zip(1..)
+        .map(|(simulation, id)| (SimulationId(id), simulation.settings.clone()))
+        .collect();
+    app.insert_resource(SimulationOverrides(overrides))
+        .add_system_to_stage(CoreStage::PreUpdate, assign_main_simulation);
+}
+
+fn assign_main_simulation(
+    mut commands: Commands,
+    query: Query<Entity, (With<ColorId>, Without<SimulationId>)>,
+) {
+    for entity in &query {
+        commands.entity(entity).insert(SimulationId::MAIN);
+    }
+}
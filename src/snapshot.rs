@@ -0,0 +1,669 @@
+//! Snapshots of the main simulation, for dumping and restoring its state.
+//!
+//! A snapshot stores the particles, plus the settings needed to resume the simulation. It is saved
+//! either as text, to be read and edited by hand, or, with the `binary-snapshots` feature, in a
+//! compact binary format, much smaller and faster to save and load for large states: see
+//! `benches/snapshot.rs`.
+//!
+//! # Text format
+//!
+//! Each line holds a setting, as its name then its values separated by spaces, or a particle.
+//! Empty lines and lines starting with `#` are ignored. The settings are the ones of
+//! [`PartialConfig`](crate::PartialConfig) and [`ReplayLog`](crate::ReplayLog) files, all of them
+//! being required, and the particles are given by their position, their velocity and their color,
+//! in the order of their [`ParticleIndex`]:
+//!
+//! ```text
+//! bounds -1 -1 1 1
+//! topology torus
+//! colors #ff0000ff #00ff00ff
+//! color_attractions 0.3,-0.1;0.2,0.3
+//! attraction_radius 0.04 0.4
+//! color_radius_scale 1,0.5
+//! peak_fraction 0.5
+//! kernel smoothstep
+//! smooth_cutoff 0.2
+//! anisotropy 1 1
+//! friction 0.1
+//! particle 0.1 -0.2 0 0 1
+//! particle -0.3 0.25 0.01 0 0
+//! ```
+//!
+//! Colors are saved with 8 bits per channel, and positions and velocities exactly.
+//!
+//! # Binary format
+//!
+//! Positions, velocities and colors are stored as packed arrays. All values are little-endian:
+//!
+//! - the magic bytes `PLSNAP`, the format version as a `u8`, then the size in bytes of the
+//!   scalars of the particles as a `u8`: `4` for `f32` and `8` for `f64`, see
+//!   [`Real`](crate::Real),
+//! - the [`WorldBounds`] as `min.x`, `min.y`, `max.x`, `max.y`, the [`AttractionRadius`] as
+//!   `rmin`, `rmax`, the [`PeakFraction`] and the [`Friction`], all as `f32`,
+//! - the [`Kernel`] as a `u8`: `0` for tent, `1` for smoothstep and `2` for gaussian,
+//! - the [`Topology`] of the world as a `u8`: `0` for a torus, `1` for a cylinder along `x`, `2`
+//!   for a cylinder along `y` and `3` for a Klein bottle,
+//! - the colors as a `u32` number of colors, each color being its red, green, blue and alpha
+//!   channels as `f32`, the [`ColorRadiusScale`] as a `u32` number of scales followed by the
+//!   scales as `f32`, the window of the [`SmoothCutoff`] and the [`Anisotropy`] as `x`, `y`, all
+//!   as `f32`,
+//! - the [`ColorAttractions`] as a `u32` number of rows, each row being a `u32` length followed by
+//!   its entries as `f32`,
+//! - the `u32` number of particles `n`, then the `2 * n` coordinates of their positions, the
+//!   `2 * n` coordinates of their velocities, and their `n` colors as `u32`.
+//!
+//! Snapshots saved with either precision can be loaded with the other.
+
+use bevy::prelude::*;
+
+use std::{error::Error, fmt, io, path::Path, str::FromStr};
+
+#[cfg(feature = "binary-snapshots")]
+use crate::{
+    precision::{real, real_from_f64},
+    Attraction, Real,
+};
+use crate::{
+    replay::{ReplayEvent, Tokens},
+    svg::hex,
+    Anisotropy, AttractionRadius, ColorAttractions, ColorId, ColorRadiusScale, Friction, Kernel,
+    Particle, ParticleColors, ParticleIndex, ParticleLifeError, ParticleLifePlugin, PeakFraction,
+    Position, RealVec2, SimulationId, SmoothCutoff, Topology, Velocity, WorldBounds,
+};
+
+#[cfg(feature = "binary-snapshots")]
+const MAGIC: &[u8; 6] = b"PLSNAP";
+#[cfg(feature = "binary-snapshots")]
+const VERSION: u8 = 1;
+
+/// The state of the main simulation and its settings, see the [module documentation](self) for
+/// the formats.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimulationSnapshot {
+    pub bounds: WorldBounds,
+    /// The colors of the particles, left unchanged by [`Self::apply`] when empty.
+    pub colors: Vec<Color>,
+    pub attraction_radius: AttractionRadius,
+    pub color_radius_scale: ColorRadiusScale,
+    pub color_attractions: ColorAttractions,
+    pub peak_fraction: PeakFraction,
+    pub kernel: Kernel,
+    pub smooth_cutoff: SmoothCutoff,
+    pub anisotropy: Anisotropy,
+    pub friction: Friction,
+    /// The particles, in the order of their [`ParticleIndex`].
+    pub particles: Vec<Particle>,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// The file doesn't start with the magic bytes of a snapshot.
+    NotASnapshot,
+    UnsupportedVersion(u8),
+    /// The file is truncated or holds invalid values.
+    Corrupted,
+    /// The text snapshot doesn't set a setting.
+    MissingSetting(&'static str),
+    /// Line `line` of the text snapshot, counting from `1`, is malformed.
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::NotASnapshot => write!(f, "not a simulation snapshot"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {version}")
+            }
+            Self::Corrupted => write!(f, "corrupted snapshot"),
+            Self::MissingSetting(setting) => write!(f, "`{setting}` is not set"),
+            Self::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl SimulationSnapshot {
+    /// Captures the particles of the main simulation and the global settings from `world`.
+    pub fn capture(world: &mut World) -> Self {
+        let mut particles = world
+            .query::<(
+                &ParticleIndex,
+                &Position,
+                &Velocity,
+                &ColorId,
+                &SimulationId,
+            )>()
+            .iter(world)
+            .filter(|(.., &simulation)| simulation == SimulationId::MAIN)
+            .map(|(&index, &position, &velocity, &color, _)| {
+                (
+                    index,
+                    Particle {
+                        position,
+                        velocity,
+                        color,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        particles.sort_by_key(|&(index, _)| index);
+
+        Self {
+            bounds: *world.resource(),
+            colors: world.resource::<ParticleColors>().0.clone(),
+            attraction_radius: *world.resource(),
+            color_radius_scale: world.resource::<ColorRadiusScale>().clone(),
+            color_attractions: world.resource::<ColorAttractions>().clone(),
+            peak_fraction: *world.resource(),
+            kernel: *world.resource(),
+            smooth_cutoff: *world.resource(),
+            anisotropy: *world.resource(),
+            friction: *world.resource(),
+            particles: particles
+                .into_iter()
+                .map(|(_, particle)| particle)
+                .collect(),
+        }
+    }
+
+    /// Sets up `plugin` to resume the simulation from this snapshot.
+    pub fn apply(self, plugin: &mut ParticleLifePlugin) {
+        plugin.bounds = self.bounds;
+        if !self.colors.is_empty() {
+            plugin.colors = self.colors;
+        }
+        plugin.attraction_radius = self.attraction_radius;
+        plugin.color_radius_scale = self.color_radius_scale;
+        plugin.color_attractions = self.color_attractions;
+        plugin.peak_fraction = self.peak_fraction;
+        plugin.kernel = self.kernel;
+        plugin.smooth_cutoff = self.smooth_cutoff;
+        plugin.anisotropy = self.anisotropy;
+        plugin.friction = self.friction;
+        plugin.initial_particles = self.particles;
+    }
+
+    pub fn save_text(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+
+    pub fn load_text(path: impl AsRef<Path>) -> Result<Self, ParticleLifeError> {
+        Ok(std::fs::read_to_string(path)?.parse::<Self>()?)
+    }
+
+    #[cfg(feature = "binary-snapshots")]
+    pub fn save_binary(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    #[cfg(feature = "binary-snapshots")]
+    pub fn load_binary(path: impl AsRef<Path>) -> Result<Self, ParticleLifeError> {
+        Ok(Self::from_bytes(&std::fs::read(path)?)?)
+    }
+
+    #[cfg(feature = "binary-snapshots")]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let particle_size = 4 * std::mem::size_of::<Real>() + 4;
+        let mut bytes = Vec::with_capacity(64 + self.particles.len() * particle_size);
+        bytes.extend(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(std::mem::size_of::<Real>() as u8);
+
+        let settings = [
+            self.bounds.min.x,
+            self.bounds.min.y,
+            self.bounds.max.x,
+            self.bounds.max.y,
+            self.attraction_radius.rmin,
+            self.attraction_radius.rmax,
+            self.peak_fraction.0,
+            self.friction.0,
+        ];
+        for value in settings {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.push(match self.kernel {
+            Kernel::Tent => 0,
+            Kernel::Smoothstep => 1,
+            Kernel::Gaussian => 2,
+        });
+        bytes.push(match self.bounds.topology {
+            Topology::Torus => 0,
+            Topology::CylinderX => 1,
+            Topology::CylinderY => 2,
+            Topology::Klein => 3,
+        });
+
+        bytes.extend((self.colors.len() as u32).to_le_bytes());
+        for color in &self.colors {
+            for channel in color.as_rgba_f32() {
+                bytes.extend(channel.to_le_bytes());
+            }
+        }
+        bytes.extend((self.color_radius_scale.0.len() as u32).to_le_bytes());
+        for scale in &self.color_radius_scale.0 {
+            bytes.extend(scale.to_le_bytes());
+        }
+        for value in [
+            self.smooth_cutoff.window,
+            self.anisotropy.x,
+            self.anisotropy.y,
+        ] {
+            bytes.extend(value.to_le_bytes());
+        }
+
+        bytes.extend((self.color_attractions.0.len() as u32).to_le_bytes());
+        for row in &self.color_attractions.0 {
+            bytes.extend((row.len() as u32).to_le_bytes());
+            for attraction in row {
+                bytes.extend(attraction.0.to_le_bytes());
+            }
+        }
+
+        bytes.extend((self.particles.len() as u32).to_le_bytes());
+        for particle in &self.particles {
+            bytes.extend(particle.position.0.x.to_le_bytes());
+            bytes.extend(particle.position.0.y.to_le_bytes());
+        }
+        for particle in &self.particles {
+            bytes.extend(particle.velocity.0.x.to_le_bytes());
+            bytes.extend(particle.velocity.0.y.to_le_bytes());
+        }
+        for particle in &self.particles {
+            bytes.extend((particle.color.0 as u32).to_le_bytes());
+        }
+        bytes
+    }
+
+    #[cfg(feature = "binary-snapshots")]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let real_size = reader.u8()?;
+        if real_size != 4 && real_size != 8 {
+            return Err(SnapshotError::Corrupted);
+        }
+
+        let (min, max) = (
+            Vec2::new(reader.f32()?, reader.f32()?),
+            Vec2::new(reader.f32()?, reader.f32()?),
+        );
+        let attraction_radius = AttractionRadius {
+            rmin: reader.f32()?,
+            rmax: reader.f32()?,
+        };
+        let peak_fraction = PeakFraction(reader.f32()?);
+        let friction = Friction(reader.f32()?);
+        let kernel = match reader.u8()? {
+            0 => Kernel::Tent,
+            1 => Kernel::Smoothstep,
+            2 => Kernel::Gaussian,
+            _ => return Err(SnapshotError::Corrupted),
+        };
+        let topology = match reader.u8()? {
+            0 => Topology::Torus,
+            1 => Topology::CylinderX,
+            2 => Topology::CylinderY,
+            3 => Topology::Klein,
+            _ => return Err(SnapshotError::Corrupted),
+        };
+        let bounds = WorldBounds { min, max, topology };
+
+        let count = reader.u32()?;
+        let colors = (0..count)
+            .map(|_| {
+                Ok(Color::rgba(
+                    reader.f32()?,
+                    reader.f32()?,
+                    reader.f32()?,
+                    reader.f32()?,
+                ))
+            })
+            .collect::<Result<_, SnapshotError>>()?;
+        let count = reader.u32()?;
+        let color_radius_scale =
+            ColorRadiusScale((0..count).map(|_| reader.f32()).collect::<Result<_, _>>()?);
+        let smooth_cutoff = SmoothCutoff {
+            window: reader.f32()?,
+        };
+        let anisotropy = Anisotropy {
+            x: reader.f32()?,
+            y: reader.f32()?,
+        };
+
+        let rows = reader.u32()?;
+        let color_attractions = (0..rows)
+            .map(|_| {
+                let len = reader.u32()?;
+                (0..len).map(|_| reader.f32().map(Attraction)).collect()
+            })
+            .collect::<Result<_, _>>()?;
+
+        let count = reader.u32()? as usize;
+        let vectors = |reader: &mut Reader| {
+            (0..count)
+                .map(|_| {
+                    Ok(RealVec2::new(
+                        reader.real(real_size)?,
+                        reader.real(real_size)?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, SnapshotError>>()
+        };
+        let positions = vectors(&mut reader)?;
+        let velocities = vectors(&mut reader)?;
+        let particles = positions
+            .into_iter()
+            .zip(velocities)
+            .map(|(position, velocity)| {
+                Ok(Particle {
+                    position: Position(position),
+                    velocity: Velocity(velocity),
+                    color: ColorId(reader.u32()? as usize),
+                })
+            })
+            .collect::<Result<_, SnapshotError>>()?;
+        if !reader.0.is_empty() {
+            return Err(SnapshotError::Corrupted);
+        }
+
+        Ok(Self {
+            bounds,
+            colors,
+            attraction_radius,
+            color_radius_scale,
+            color_attractions: ColorAttractions(color_attractions),
+            peak_fraction,
+            kernel,
+            smooth_cutoff,
+            anisotropy,
+            friction,
+            particles,
+        })
+    }
+}
+
+impl fmt::Display for SimulationSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let WorldBounds { min, max, topology } = self.bounds;
+        writeln!(f, "bounds {} {} {} {}", min.x, min.y, max.x, max.y)?;
+        let topology = match topology {
+            Topology::Torus => "torus",
+            Topology::CylinderX => "cylinder_x",
+            Topology::CylinderY => "cylinder_y",
+            Topology::Klein => "klein",
+        };
+        writeln!(f, "topology {topology}")?;
+        write!(f, "colors")?;
+        for &color in &self.colors {
+            write!(f, " {}", hex(color))?;
+        }
+        writeln!(f)?;
+
+        // The settings shared with replays are written like their events
+        let settings = [
+            ReplayEvent::ColorAttractions(self.color_attractions.clone()),
+            ReplayEvent::AttractionRadius(self.attraction_radius),
+            ReplayEvent::ColorRadiusScale(self.color_radius_scale.clone()),
+            ReplayEvent::PeakFraction(self.peak_fraction),
+            ReplayEvent::Kernel(self.kernel),
+            ReplayEvent::SmoothCutoff(self.smooth_cutoff),
+            ReplayEvent::Anisotropy(self.anisotropy),
+            ReplayEvent::Friction(self.friction),
+        ];
+        for setting in settings {
+            writeln!(f, "{setting}")?;
+        }
+
+        for particle in &self.particles {
+            writeln!(
+                f,
+                "particle {} {} {} {} {}",
+                particle.position.0.x,
+                particle.position.0.y,
+                particle.velocity.0.x,
+                particle.velocity.0.y,
+                particle.color.0,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The settings of a text snapshot read so far.
+#[derive(Default)]
+struct TextSettings {
+    bounds: Option<(Vec2, Vec2)>,
+    topology: Option<Topology>,
+    colors: Option<Vec<Color>>,
+    color_attractions: Option<ColorAttractions>,
+    attraction_radius: Option<AttractionRadius>,
+    color_radius_scale: Option<ColorRadiusScale>,
+    peak_fraction: Option<PeakFraction>,
+    kernel: Option<Kernel>,
+    smooth_cutoff: Option<SmoothCutoff>,
+    anisotropy: Option<Anisotropy>,
+    friction: Option<Friction>,
+}
+
+impl TextSettings {
+    fn parse_line(&mut self, line: &str, particles: &mut Vec<Particle>) -> Result<(), String> {
+        let mut tokens = Tokens(line.split_whitespace());
+        match tokens.next()? {
+            "particle" => particles.push(Particle {
+                position: Position(RealVec2::new(tokens.parse()?, tokens.parse()?)),
+                velocity: Velocity(RealVec2::new(tokens.parse()?, tokens.parse()?)),
+                color: ColorId(tokens.parse()?),
+            }),
+            "bounds" => {
+                self.bounds = Some((
+                    Vec2::new(tokens.parse()?, tokens.parse()?),
+                    Vec2::new(tokens.parse()?, tokens.parse()?),
+                ))
+            }
+            "topology" => self.topology = Some(tokens.topology()?),
+            "colors" => self.colors = Some(tokens.colors()?),
+            setting @ ("color_attractions" | "attraction_radius" | "color_radius_scale"
+            | "peak_fraction" | "kernel" | "smooth_cutoff" | "anisotropy"
+            | "friction") => {
+                // These settings are written like the events of a replay
+                tokens = Tokens(line.split_whitespace());
+                match ReplayEvent::parse(&mut tokens)? {
+                    ReplayEvent::ColorAttractions(attractions) => {
+                        self.color_attractions = Some(attractions)
+                    }
+                    ReplayEvent::AttractionRadius(radius) => self.attraction_radius = Some(radius),
+                    ReplayEvent::ColorRadiusScale(scale) => self.color_radius_scale = Some(scale),
+                    ReplayEvent::PeakFraction(peak_fraction) => {
+                        self.peak_fraction = Some(peak_fraction)
+                    }
+                    ReplayEvent::Kernel(kernel) => self.kernel = Some(kernel),
+                    ReplayEvent::SmoothCutoff(smooth_cutoff) => {
+                        self.smooth_cutoff = Some(smooth_cutoff)
+                    }
+                    ReplayEvent::Anisotropy(anisotropy) => self.anisotropy = Some(anisotropy),
+                    ReplayEvent::Friction(friction) => self.friction = Some(friction),
+                    event => unreachable!("`{setting}` was parsed as {event:?}"),
+                }
+            }
+            setting => return Err(format!("unknown setting `{setting}`")),
+        }
+        tokens.finish()
+    }
+}
+
+impl FromStr for SimulationSnapshot {
+    type Err = SnapshotError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut settings = TextSettings::default();
+        let mut particles = Vec::new();
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            settings
+                .parse_line(line, &mut particles)
+                .map_err(|message| SnapshotError::Parse {
+                    line: index + 1,
+                    message,
+                })?;
+        }
+
+        fn required<T>(setting: Option<T>, name: &'static str) -> Result<T, SnapshotError> {
+            setting.ok_or(SnapshotError::MissingSetting(name))
+        }
+        let (min, max) = required(settings.bounds, "bounds")?;
+        Ok(Self {
+            bounds: WorldBounds {
+                min,
+                max,
+                topology: required(settings.topology, "topology")?,
+            },
+            colors: required(settings.colors, "colors")?,
+            attraction_radius: required(settings.attraction_radius, "attraction_radius")?,
+            color_radius_scale: required(settings.color_radius_scale, "color_radius_scale")?,
+            color_attractions: required(settings.color_attractions, "color_attractions")?,
+            peak_fraction: required(settings.peak_fraction, "peak_fraction")?,
+            kernel: required(settings.kernel, "kernel")?,
+            smooth_cutoff: required(settings.smooth_cutoff, "smooth_cutoff")?,
+            anisotropy: required(settings.anisotropy, "anisotropy")?,
+            friction: required(settings.friction, "friction")?,
+            particles,
+        })
+    }
+}
+
+/// Reads little-endian values from the start of a byte slice.
+#[cfg(feature = "binary-snapshots")]
+struct Reader<'a>(&'a [u8]);
+
+#[cfg(feature = "binary-snapshots")]
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.0.len() < len {
+            return Err(SnapshotError::Corrupted);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        Ok(self.take(N)?.try_into().expect("`take` returns `N` bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, SnapshotError> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64, SnapshotError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    /// Reads a scalar of `size` bytes, converted to the precision of the physics state.
+    fn real(&mut self, size: u8) -> Result<Real, SnapshotError> {
+        match size {
+            4 => self.f32().map(real),
+            8 => self.f64().map(real_from_f64),
+            _ => Err(SnapshotError::Corrupted),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{precision::real_vec2, Attraction};
+
+    fn snapshot() -> SimulationSnapshot {
+        SimulationSnapshot {
+            bounds: WorldBounds {
+                topology: Topology::Klein,
+                ..Default::default()
+            },
+            colors: vec![Color::RED, Color::GREEN],
+            attraction_radius: AttractionRadius {
+                rmin: 0.04,
+                rmax: 0.4,
+            },
+            color_radius_scale: ColorRadiusScale(vec![1.0, 0.5]),
+            color_attractions: ColorAttractions(vec![
+                vec![Attraction(0.3), Attraction(-0.1)],
+                vec![Attraction(0.2), Attraction(0.3)],
+            ]),
+            peak_fraction: PeakFraction(0.4),
+            kernel: Kernel::Gaussian,
+            smooth_cutoff: SmoothCutoff { window: 0.2 },
+            anisotropy: Anisotropy { x: 2.0, y: 1.0 },
+            friction: Friction(0.1),
+            particles: (0..10)
+                .map(|i| Particle {
+                    position: Position(real_vec2(Vec2::new(i as f32 / 7.0, -0.3))),
+                    velocity: Velocity(real_vec2(Vec2::new(0.01, i as f32 / 3.0))),
+                    color: ColorId(i % 2),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn text_snapshots_round_trip() {
+        let snapshot = snapshot();
+        let text = snapshot.to_string();
+        assert_eq!(text.parse::<SimulationSnapshot>().unwrap(), snapshot);
+
+        let text = text.replace("anisotropy 2 1\n", "");
+        assert!(matches!(
+            text.parse::<SimulationSnapshot>(),
+            Err(SnapshotError::MissingSetting("anisotropy"))
+        ));
+    }
+
+    #[cfg(feature = "binary-snapshots")]
+    #[test]
+    fn binary_snapshots_round_trip() {
+        let snapshot = snapshot();
+        let bytes = snapshot.to_bytes();
+        assert_eq!(SimulationSnapshot::from_bytes(&bytes).unwrap(), snapshot);
+        assert!(matches!(
+            SimulationSnapshot::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(SnapshotError::Corrupted)
+        ));
+    }
+}
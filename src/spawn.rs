@@ -0,0 +1,430 @@
+//! Helpers generating initial particles in various shapes, for
+//! [`ParticleLifePlugin::initial_particles`](crate::ParticleLifePlugin::initial_particles).
+//!
+//! All particles are generated at rest, within the [`WorldBounds`]: positions falling outside are
+//! resampled, and clamped into the bounds if that keeps failing.
+
+use bevy::prelude::*;
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    Rng,
+};
+
+use std::{cmp::Ordering, f32::consts::TAU};
+
+use crate::{logging::LOG_TARGET, precision::real_vec2, ColorId, Particle, Position, WorldBounds};
+
+/// How many times a position outside of the world is resampled before being clamped.
+const MAX_RESAMPLES: usize = 16;
+
+/// How many candidate positions [`spawn_poisson`] tries per requested particle before giving up.
+const POISSON_ATTEMPTS: usize = 30;
+
+/// Spawns `count` particles uniformly in `region`. Regions with `min` above `max` are read from
+/// their corners, and regions without width or height spawn particles along a line or at a point.
+pub fn spawn_in_rect(
+    region: Rect,
+    count: usize,
+    color: ColorId,
+    bounds: &WorldBounds,
+    rng: &mut impl Rng,
+) -> Vec<Particle> {
+    let region = Rect::from_corners(region.min, region.max);
+    spawn_with(count, color, bounds, rng, |rng| {
+        Vec2::new(
+            rng.gen_range(region.min.x..=region.max.x),
+            rng.gen_range(region.min.y..=region.max.y),
+        )
+    })
+}
+
+/// Spawns `count` particles uniformly in the disc centered on `center`.
+pub fn spawn_in_disc(
+    center: Vec2,
+    radius: f32,
+    count: usize,
+    color: ColorId,
+    bounds: &WorldBounds,
+    rng: &mut impl Rng,
+) -> Vec<Particle> {
+    spawn_in_ring(center, 0.0, radius, count, color, bounds, rng)
+}
+
+/// Spawns `count` particles uniformly in the ring centered on `center`, between the radii `inner`
+/// and `outer`, in either order. Negative radii count as `0`.
+pub fn spawn_in_ring(
+    center: Vec2,
+    inner: f32,
+    outer: f32,
+    count: usize,
+    color: ColorId,
+    bounds: &WorldBounds,
+    rng: &mut impl Rng,
+) -> Vec<Particle> {
+    let (inner, outer) = (inner.max(0.0), outer.max(0.0));
+    let (inner, outer) = (inner.min(outer), inner.max(outer));
+    spawn_with(count, color, bounds, rng, |rng| {
+        // The density is uniform over the area when the squared radius is uniform
+        let radius = f32::sqrt(rng.gen_range(inner * inner..=outer * outer));
+        let angle = rng.gen_range(0.0..TAU);
+        center + radius * Vec2::from_angle(angle)
+    })
+}
+
+/// Spawns `count` particles following a 2D normal distribution centered on `center`, with the
+/// standard deviation `std` along each axis.
+pub fn spawn_gaussian(
+    center: Vec2,
+    std: f32,
+    count: usize,
+    color: ColorId,
+    bounds: &WorldBounds,
+    rng: &mut impl Rng,
+) -> Vec<Particle> {
+    spawn_with(count, color, bounds, rng, |rng| {
+        // Box-Muller transform
+        let radius = f32::sqrt(-2.0 * f32::ln(1.0 - rng.gen::<f32>()));
+        let angle = rng.gen_range(0.0..TAU);
+        center + std * radius * Vec2::from_angle(angle)
+    })
+}
+
+/// Spawns up to `count` particles uniformly in `region`, each at least `min_spacing` away from the
+/// others, so that no two particles start on top of each other and violently repel.
+///
+/// Candidate positions are drawn at random and rejected when too close to an accepted one. When
+/// the region can't fit `count` particles that far apart, fewer are returned and a warning is
+/// logged. Only the part of `region` within the world is filled, and spacings are measured
+/// without wrapping around the edges of the world. Without a positive `min_spacing`, the particles
+/// are spawned like with [`spawn_in_rect`].
+pub fn spawn_poisson(
+    region: Rect,
+    count: usize,
+    min_spacing: f32,
+    color: ColorId,
+    bounds: &WorldBounds,
+    rng: &mut impl Rng,
+) -> Vec<Particle> {
+    let region = Rect {
+        min: region.min.max(bounds.min),
+        max: region.max.min(bounds.max),
+    };
+    if region.min.cmpgt(region.max).any() {
+        warn!(target: LOG_TARGET, event = "spawn_fell_short", requested = count, spawned = 0);
+        return Vec::new();
+    }
+    // Also catches NaN spacings, which would make every cell size NaN
+    if min_spacing.partial_cmp(&0.0) != Some(Ordering::Greater) {
+        return spawn_in_rect(region, count, color, bounds, rng);
+    }
+
+    // Cells small enough to hold at most one particle, so that only the cells up to 2 away from
+    // a candidate can hold particles too close to it
+    let cell_size = min_spacing / std::f32::consts::SQRT_2;
+    let columns = (region.width() / cell_size).ceil().max(1.0) as usize;
+    let rows = (region.height() / cell_size).ceil().max(1.0) as usize;
+    let mut cells = vec![None::<Vec2>; columns * rows];
+    let cell_of = |position: Vec2| {
+        let cell = ((position - region.min) / cell_size).as_uvec2();
+        (
+            (cell.x as usize).min(columns - 1),
+            (cell.y as usize).min(rows - 1),
+        )
+    };
+
+    let mut positions = Vec::new();
+    for _ in 0..count * POISSON_ATTEMPTS {
+        if positions.len() == count {
+            break;
+        }
+        let candidate = Vec2::new(
+            rng.gen_range(region.min.x..=region.max.x),
+            rng.gen_range(region.min.y..=region.max.y),
+        );
+
+        let (column, row) = cell_of(candidate);
+        let too_close = (row.saturating_sub(2)..(row + 3).min(rows)).any(|row| {
+            (column.saturating_sub(2)..(column + 3).min(columns)).any(|column| {
+                cells[row * columns + column]
+                    .is_some_and(|other: Vec2| other.distance(candidate) < min_spacing)
+            })
+        });
+        if !too_close {
+            cells[row * columns + column] = Some(candidate);
+            positions.push(candidate);
+        }
+    }
+
+    if positions.len() < count {
+        warn!(
+            target: LOG_TARGET,
+            event = "spawn_fell_short",
+            requested = count,
+            spawned = positions.len(),
+            min_spacing,
+        );
+    }
+    positions
+        .into_iter()
+        .map(|position| Particle {
+            position: Position(real_vec2(position)),
+            velocity: Default::default(),
+            color,
+        })
+        .collect()
+}
+
+/// Spawns `count` particles over the whole world following the density given by `grid`, such as
+/// the pixels of an image or the cells of a spreadsheet, to seed a shape.
+///
+/// `grid[i][j]` is the relative density of the cell in row `i` from the top and column `j` from
+/// the left, the cells evenly dividing the world, and each particle lands uniformly within a cell
+/// picked with a probability proportional to its density. Rows shorter than the longest one are
+/// padded with empty cells. When the densities don't add up to a positive total or some are
+/// negative, no particle is spawned and a warning is logged.
+pub fn spawn_from_density(
+    grid: &[Vec<f32>],
+    count: usize,
+    color: ColorId,
+    bounds: &WorldBounds,
+    rng: &mut impl Rng,
+) -> Vec<Particle> {
+    let rows = grid.len();
+    let columns = grid.iter().map(Vec::len).max().unwrap_or(0);
+    let weights = grid.iter().flat_map(|row| {
+        row.iter()
+            .copied()
+            .chain(std::iter::repeat_n(0.0, columns - row.len()))
+    });
+    let cells = match WeightedIndex::new(weights) {
+        Ok(cells) => cells,
+        Err(error) => {
+            warn!(
+                target: LOG_TARGET,
+                event = "spawn_fell_short",
+                requested = count,
+                spawned = 0,
+                %error,
+            );
+            return Vec::new();
+        }
+    };
+
+    let cell_size = bounds.size() / Vec2::new(columns as f32, rows as f32);
+    spawn_with(count, color, bounds, rng, |rng| {
+        let cell = cells.sample(rng);
+        let (row, column) = (cell / columns, cell % columns);
+        // Rows go down from the top of the world
+        let corner = Vec2::new(
+            bounds.min.x + column as f32 * cell_size.x,
+            bounds.max.y - (row + 1) as f32 * cell_size.y,
+        );
+        corner + cell_size * Vec2::new(rng.gen(), rng.gen())
+    })
+}
+
+fn spawn_with<R: Rng>(
+    count: usize,
+    color: ColorId,
+    bounds: &WorldBounds,
+    rng: &mut R,
+    mut sample: impl FnMut(&mut R) -> Vec2,
+) -> Vec<Particle> {
+    (0..count)
+        .map(|_| {
+            let mut position = sample(rng);
+            for _ in 0..MAX_RESAMPLES {
+                if bounds.contains(position) {
+                    break;
+                }
+                position = sample(rng);
+            }
+
+            Particle {
+                position: Position(real_vec2(bounds.clamp(position))),
+                velocity: Default::default(),
+                color,
+            }
+        })
+        .collect()
+}
+
+/// A builder combining the spawn helpers of this module into a single set of particles.
+#[derive(Debug, Clone, Default)]
+pub struct ParticleSet {
+    bounds: WorldBounds,
+    particles: Vec<Particle>,
+}
+
+impl ParticleSet {
+    pub fn new(bounds: WorldBounds) -> Self {
+        Self {
+            bounds,
+            particles: Vec::new(),
+        }
+    }
+
+    /// See [`spawn_in_rect`].
+    pub fn rect(self, region: Rect, count: usize, color: ColorId, rng: &mut impl Rng) -> Self {
+        let particles = spawn_in_rect(region, count, color, &self.bounds, rng);
+        self.with(particles)
+    }
+
+    /// See [`spawn_poisson`].
+    pub fn poisson(
+        self,
+        region: Rect,
+        count: usize,
+        min_spacing: f32,
+        color: ColorId,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let particles = spawn_poisson(region, count, min_spacing, color, &self.bounds, rng);
+        self.with(particles)
+    }
+
+    /// See [`spawn_in_disc`].
+    pub fn disc(
+        self,
+        center: Vec2,
+        radius: f32,
+        count: usize,
+        color: ColorId,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let particles = spawn_in_disc(center, radius, count, color, &self.bounds, rng);
+        self.with(particles)
+    }
+
+    /// See [`spawn_in_ring`].
+    pub fn ring(
+        self,
+        center: Vec2,
+        inner: f32,
+        outer: f32,
+        count: usize,
+        color: ColorId,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let particles = spawn_in_ring(center, inner, outer, count, color, &self.bounds, rng);
+        self.with(particles)
+    }
+
+    /// See [`spawn_from_density`].
+    pub fn density(
+        self,
+        grid: &[Vec<f32>],
+        count: usize,
+        color: ColorId,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let particles = spawn_from_density(grid, count, color, &self.bounds, rng);
+        self.with(particles)
+    }
+
+    /// See [`spawn_gaussian`].
+    pub fn gaussian(
+        self,
+        center: Vec2,
+        std: f32,
+        count: usize,
+        color: ColorId,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let particles = spawn_gaussian(center, std, count, color, &self.bounds, rng);
+        self.with(particles)
+    }
+
+    /// Adds arbitrary particles to the set.
+    pub fn with(mut self, particles: impl IntoIterator<Item = Particle>) -> Self {
+        self.particles.extend(particles);
+        self
+    }
+
+    pub fn into_particles(self) -> Vec<Particle> {
+        self.particles
+    }
+}
+
+impl From<ParticleSet> for Vec<Particle> {
+    fn from(set: ParticleSet) -> Self {
+        set.into_particles()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::precision::single_vec2;
+
+    fn positions(particles: &[Particle]) -> Vec<Vec2> {
+        particles
+            .iter()
+            .map(|particle| single_vec2(particle.position.0))
+            .collect()
+    }
+
+    #[test]
+    fn poisson_particles_are_min_spacing_apart() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let region = Rect::new(-0.5, -0.5, 0.5, 0.5);
+        let particles = spawn_poisson(
+            region,
+            200,
+            0.05,
+            ColorId(0),
+            &WorldBounds::default(),
+            &mut rng,
+        );
+        assert_eq!(particles.len(), 200);
+
+        let positions = positions(&particles);
+        for (i, &a) in positions.iter().enumerate() {
+            assert!(region.contains(a), "{a} is out of the region");
+            for &b in &positions[i + 1..] {
+                assert!(a.distance(b) >= 0.05, "{a} and {b} are too close");
+            }
+        }
+    }
+
+    #[test]
+    fn poisson_returns_fewer_particles_when_they_dont_fit() {
+        let mut rng = StdRng::seed_from_u64(0);
+        // At most 4 particles fit in the corners of the square
+        let particles = spawn_poisson(
+            Rect::new(0.0, 0.0, 0.1, 0.1),
+            10,
+            0.1,
+            ColorId(0),
+            &WorldBounds::default(),
+            &mut rng,
+        );
+        assert!(
+            (1..=4).contains(&particles.len()),
+            "{} particles",
+            particles.len()
+        );
+    }
+
+    #[test]
+    fn density_spreads_particles_by_cell_weight() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let particles = spawn_from_density(
+            &[vec![1.0, 3.0]],
+            10_000,
+            ColorId(0),
+            &WorldBounds::default(),
+            &mut rng,
+        );
+        assert_eq!(particles.len(), 10_000);
+
+        let right = positions(&particles)
+            .iter()
+            .filter(|position| position.x > 0.0)
+            .count();
+        let fraction = right as f32 / 10_000.0;
+        assert!((fraction - 0.75).abs() < 0.02, "{fraction} on the right");
+    }
+}
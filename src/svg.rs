@@ -0,0 +1,194 @@
+//! Exporting frames as SVG images, for vector figures independent of the renderer.
+
+use bevy::prelude::*;
+
+use std::{
+    fmt::Write as _,
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    logging::LOG_TARGET, precision::single_vec2, ColorId, Particle, ParticleColors, ParticleIndex,
+    Position, SimulationId, Velocity, WorldBounds, PARTICLE_RADIUS,
+};
+
+/// Send this event to export the particles of the main simulation with [`export_svg`], to the
+/// first of `screenshot_0.svg`, `screenshot_1.svg`, etc. that doesn't exist yet in the working
+/// directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TakeScreenshot;
+
+pub(crate) fn build(app: &mut App) {
+    app.add_event::<TakeScreenshot>()
+        .add_system_to_stage(CoreStage::PostUpdate, take_screenshots);
+}
+
+/// Writes `particles` as an SVG image of the world, each particle being a circle of its color
+/// among `colors`, as large as it is drawn on screen. The image spans exactly `bounds`, with the
+/// `y` axis pointing up like in the world.
+///
+/// Particles whose color is out of `colors` are drawn in magenta.
+pub fn export_svg(
+    path: impl AsRef<Path>,
+    particles: &[Particle],
+    colors: &[Color],
+    bounds: &WorldBounds,
+) -> io::Result<()> {
+    std::fs::write(path, to_svg(particles, colors, bounds))
+}
+
+/// Returns the SVG image written by [`export_svg`].
+pub fn to_svg(particles: &[Particle], colors: &[Color], bounds: &WorldBounds) -> String {
+    let size = bounds.size();
+    let mut svg = String::new();
+    // Flipping the `y` axis moves the top edge of the world, `max.y`, to the top of the image
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        bounds.min.x, -bounds.max.y, size.x, size.y,
+    )
+    .unwrap();
+    for particle in particles {
+        let position = single_vec2(particle.position.0);
+        let color = colors
+            .get(particle.color.0)
+            .copied()
+            .unwrap_or(Color::FUCHSIA);
+        writeln!(
+            svg,
+            r#"  <circle cx="{}" cy="{}" r="{PARTICLE_RADIUS}" fill="{}"/>"#,
+            position.x,
+            -position.y,
+            hex(color),
+        )
+        .unwrap();
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Formats `color` as `#rrggbbaa`.
+pub(crate) fn hex(color: Color) -> String {
+    let [r, g, b, a] = color
+        .as_rgba_f32()
+        .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+}
+
+fn take_screenshots(
+    mut screenshots: EventReader<TakeScreenshot>,
+    colors: Res<ParticleColors>,
+    bounds: Res<WorldBounds>,
+    query: Query<(
+        &Position,
+        &Velocity,
+        &ColorId,
+        &ParticleIndex,
+        &SimulationId,
+    )>,
+) {
+    // Several screenshots in a frame would be the same
+    if screenshots.iter().count() == 0 {
+        return;
+    }
+
+    let mut particles: Vec<_> = query
+        .iter()
+        .filter(|(.., &simulation)| simulation == SimulationId::MAIN)
+        .collect();
+    // Later particles are drawn on top, the same way in every screenshot
+    particles.sort_by_key(|&(.., index, _)| *index);
+    let particles: Vec<_> = particles
+        .into_iter()
+        .map(|(&position, &velocity, &color, ..)| Particle {
+            position,
+            velocity,
+            color,
+        })
+        .collect();
+
+    let path = (0..)
+        .map(|n| PathBuf::from(format!("screenshot_{n}.svg")))
+        .find(|path| !path.exists())
+        .expect("there are fewer files than numbers");
+    match export_svg(&path, &particles, &colors.0, &bounds) {
+        Ok(()) => info!(target: LOG_TARGET, event = "screenshot_taken", ?path),
+        Err(error) => warn!(target: LOG_TARGET, event = "screenshot_failed", ?path, %error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{precision::real_vec2, ColorId, Position, Velocity};
+
+    /// The elements of `xml` with their attributes, in document order, after checking that the
+    /// tags are well-formed and balanced.
+    fn elements(xml: &str) -> Vec<(&str, Vec<(&str, &str)>)> {
+        let (mut elements, mut open) = (Vec::new(), Vec::new());
+        let mut rest = xml.trim();
+        while let Some(start) = rest.find('<') {
+            assert!(rest[..start].trim().is_empty(), "text in {rest}");
+            let end = rest.find('>').expect("unclosed tag");
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop(), Some(name), "mismatched closing tag");
+                continue;
+            }
+
+            let (tag, self_closing) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let (name, mut attributes) = tag.split_once(' ').unwrap_or((tag, ""));
+            let mut parsed = Vec::new();
+            while !attributes.trim().is_empty() {
+                let (key, value) = attributes.trim_start().split_once("=\"").expect(tag);
+                let (value, remaining) = value.split_once('"').expect(tag);
+                parsed.push((key, value));
+                attributes = remaining;
+            }
+            elements.push((name, parsed));
+            if !self_closing {
+                open.push(name);
+            }
+        }
+        assert!(rest.trim().is_empty() && open.is_empty(), "unbalanced tags");
+        elements
+    }
+
+    #[test]
+    fn svg_is_well_formed_with_a_circle_per_particle() {
+        let particles: Vec<_> = (0..5)
+            .map(|i| Particle {
+                position: Position(real_vec2(Vec2::new(0.1 * i as f32, -0.2))),
+                velocity: Velocity::default(),
+                color: ColorId(i % 3),
+            })
+            .collect();
+        let svg = to_svg(
+            &particles,
+            &[Color::RED, Color::GREEN],
+            &WorldBounds::default(),
+        );
+
+        let elements = elements(&svg);
+        let (root, attributes) = &elements[0];
+        assert_eq!(*root, "svg");
+        assert!(attributes.contains(&("xmlns", "http://www.w3.org/2000/svg")));
+        let circles: Vec<_> = elements[1..]
+            .iter()
+            .map(|(name, attributes)| {
+                assert_eq!(*name, "circle");
+                attributes
+            })
+            .collect();
+        assert_eq!(circles.len(), particles.len());
+        assert!(circles[1].contains(&("cy", "0.2")));
+        assert!(circles[1].contains(&("fill", "#00ff00ff")));
+        // Out of the palette
+        assert!(circles[2].contains(&("fill", "#ff00ffff")));
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+use crate::{
+    lines::Lines, precision::single_vec2, ColorId, ParticleColors, Position, SimulationId,
+    Velocity, WorldBounds,
+};
+
+/// Draws a short line from each particle of the main simulation in its direction of motion, a
+/// cheaper way than trails to show the local flow.
+///
+/// Each tick is `scale` times the velocity of its particle, in the color of the particle. Ticks
+/// are cut at the edge of the world rather than drawn across it.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct VelocityTicks {
+    pub enabled: bool,
+    pub scale: f32,
+}
+
+impl Default for VelocityTicks {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            scale: 0.05,
+        }
+    }
+}
+
+pub(crate) fn build(app: &mut App, velocity_ticks: VelocityTicks) {
+    app.insert_resource(velocity_ticks)
+        .add_system(draw_velocity_ticks);
+}
+
+fn draw_velocity_ticks(
+    velocity_ticks: Res<VelocityTicks>,
+    bounds: Res<WorldBounds>,
+    colors: Res<ParticleColors>,
+    mut lines: ResMut<Lines>,
+    query: Query<(&Position, &Velocity, &ColorId, &SimulationId)>,
+) {
+    if !velocity_ticks.enabled {
+        return;
+    }
+
+    for (position, velocity, color, &simulation) in &query {
+        if simulation != SimulationId::MAIN {
+            continue;
+        }
+        let Some(&color) = colors.0.get(color.0) else {
+            continue;
+        };
+        let start = single_vec2(position.0);
+        let tick = single_vec2(velocity.0) * velocity_ticks.scale;
+        lines.line(
+            start,
+            start + tick * fraction_inside(start, tick, &bounds),
+            color,
+        );
+    }
+}
+
+/// The largest fraction of `tick` that stays in `bounds` when starting from `start`.
+fn fraction_inside(start: Vec2, tick: Vec2, bounds: &WorldBounds) -> f32 {
+    let end = start + tick;
+    let mut fraction = 1.0_f32;
+    for axis in 0..2 {
+        if end[axis] > bounds.max[axis] {
+            fraction = fraction.min((bounds.max[axis] - start[axis]) / tick[axis]);
+        } else if end[axis] < bounds.min[axis] {
+            fraction = fraction.min((bounds.min[axis] - start[axis]) / tick[axis]);
+        }
+    }
+    fraction.max(0.0)
+}
@@ -0,0 +1,442 @@
+use bevy::{
+    asset::load_internal_asset,
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        camera::{RenderTarget, ScalingMode},
+        mesh::{Indices, MeshVertexBufferLayout, PrimitiveTopology},
+        render_resource::{
+            AsBindGroup, BlendComponent, BlendFactor, BlendOperation, BlendState, Extent3d,
+            RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError, TextureDimension,
+            TextureFormat, TextureUsages,
+        },
+        view::{NoFrustumCulling, RenderLayers},
+    },
+    sprite::{Material2d, Material2dKey, Material2dPlugin, MaterialMesh2dBundle},
+};
+
+use std::collections::VecDeque;
+
+use crate::{
+    lines::Lines, precision::single_vec2, update_position, ColorId, ParticleColors, Position,
+    SimulationId, WorldBounds, PARTICLE_RADIUS,
+};
+
+/// Leaves fading trails behind the particles of the main simulation.
+///
+/// Each frame, the trails are darkened by `decay`, the fraction of their brightness kept from one
+/// frame to the next, and the current position of each particle is added to them. How they are
+/// drawn depends on the [`TrailMode`].
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct TrailBuffer {
+    pub enabled: bool,
+    pub decay: f32,
+    /// The width in pixels of the texture of [`TrailMode::Texture`].
+    pub resolution: u32,
+    pub mode: TrailMode,
+}
+
+impl Default for TrailBuffer {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            decay: 0.9,
+            resolution: 512,
+            mode: TrailMode::default(),
+        }
+    }
+}
+
+/// How the trails of [`TrailBuffer`] are drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrailMode {
+    /// The particles are drawn on the GPU into a texture covering the world, which is shown under
+    /// the particles. Each frame, a fullscreen pass copies the texture of the previous frame
+    /// multiplied by the decay, and all the particles are then drawn additively on top of it in a
+    /// single draw call.
+    ///
+    /// The cost depends on the number of particles and on the resolution of the texture, but not
+    /// on the length of the trails, so this mode suits large numbers of particles. The trails are
+    /// as wide as the particles and accumulate in a floating-point texture, so faint trails fade
+    /// out smoothly and overlapping trails grow brighter.
+    #[default]
+    Texture,
+    /// Each particle draws a line through its last `length` positions, each segment fainter than
+    /// the next one by the decay.
+    ///
+    /// The trails are exact at any zoom level but cost `length` segments per particle, so this mode
+    /// suits small numbers of particles.
+    Geometry { length: usize },
+}
+
+/// The render layer of the trail pass, only seen by the camera drawing into the trail texture.
+/// Simulations use the layers from `0`, see `simulations::build`.
+const TRAIL_LAYER: u8 = RenderLayers::TOTAL_LAYERS as u8 - 1;
+
+const FADE_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 0x5305_36e6_5c8e_5dff);
+
+/// Copies the trail texture of the previous frame, multiplied by `decay`.
+#[derive(Debug, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "462be3a0-3e37-4f89-9263-e91ad4a3fc37"]
+struct FadeMaterial {
+    #[uniform(0)]
+    decay: f32,
+    #[texture(1)]
+    #[sampler(2)]
+    previous: Handle<Image>,
+}
+
+impl Material2d for FadeMaterial {
+    fn fragment_shader() -> ShaderRef {
+        FADE_SHADER_HANDLE.typed().into()
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayout,
+        _key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // The pass covers the whole texture, and replaces it
+        if let Some(fragment) = &mut descriptor.fragment {
+            for target in fragment.targets.iter_mut().flatten() {
+                target.blend = None;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds the vertex colors of a mesh to the trail texture.
+#[derive(Debug, Clone, AsBindGroup, TypeUuid)]
+#[uuid = "da639cfb-dcf5-40c6-aebb-985641b20e8b"]
+struct AdditiveMaterial {}
+
+impl Material2d for AdditiveMaterial {
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayout,
+        _key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let add = BlendComponent {
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        };
+        if let Some(fragment) = &mut descriptor.fragment {
+            for target in fragment.targets.iter_mut().flatten() {
+                target.blend = Some(BlendState {
+                    color: add,
+                    alpha: add,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The assets of [`TrailMode::Texture`].
+///
+/// The trails are drawn into the two textures in turn, each one being faded into the other.
+#[derive(Debug, Clone, Default, Resource)]
+struct TrailTextures {
+    images: [Handle<Image>; 2],
+    /// The index of the image drawn this frame.
+    current: usize,
+    fade: Handle<FadeMaterial>,
+    /// One square per particle.
+    particles: Handle<Mesh>,
+}
+
+/// Marks the camera drawing into the trail textures.
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct TrailCamera;
+
+/// Marks the sprite showing the trail texture.
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct TrailSprite;
+
+/// The last positions of a particle of the main simulation for [`TrailMode::Geometry`], the most
+/// recent first.
+#[derive(Debug, Clone, Default, Component)]
+struct TrailHistory(VecDeque<Vec2>);
+
+pub(crate) fn build(app: &mut App, trail_buffer: TrailBuffer) {
+    load_internal_asset!(
+        app,
+        FADE_SHADER_HANDLE,
+        "shaders/trail_fade.wgsl",
+        Shader::from_wgsl
+    );
+
+    app.add_plugin(Material2dPlugin::<FadeMaterial>::default())
+        .add_plugin(Material2dPlugin::<AdditiveMaterial>::default())
+        .insert_resource(trail_buffer)
+        .init_resource::<TrailTextures>()
+        .add_startup_system(setup_trail_texture)
+        .add_system(update_trail_texture.after(update_position))
+        .add_system(update_geometry_trails.after(update_position));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn setup_trail_texture(
+    mut commands: Commands,
+    trail_buffer: Res<TrailBuffer>,
+    bounds: Res<WorldBounds>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut fade_materials: ResMut<Assets<FadeMaterial>>,
+    mut additive_materials: ResMut<Assets<AdditiveMaterial>>,
+    mut textures: ResMut<TrailTextures>,
+) {
+    let size = bounds.size();
+    let width = trail_buffer.resolution.max(1);
+    let height = ((width as f32 * size.y / size.x).round() as u32).max(1);
+    let trail_images = [(); 2].map(|()| images.add(trail_image(width, height)));
+    let fade = fade_materials.add(FadeMaterial {
+        decay: 0.0,
+        previous: trail_images[1].clone(),
+    });
+    let particles = meshes.add(Mesh::new(PrimitiveTopology::TriangleList));
+
+    let (center, half_size) = (bounds.center(), size / 2.0);
+    let mut camera_bundle = Camera2dBundle::default();
+    camera_bundle.transform.translation.x = center.x;
+    camera_bundle.transform.translation.y = center.y;
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                // Drawn before the cameras of the simulations, which show the texture
+                priority: -1,
+                is_active: false,
+                // Keeps the brightness of the trails above 1 and the faint trails
+                hdr: true,
+                target: RenderTarget::Image(trail_images[0].clone()),
+                ..Default::default()
+            },
+            camera_2d: Camera2d {
+                // The fade pass covers the whole texture
+                clear_color: ClearColorConfig::None,
+            },
+            projection: OrthographicProjection {
+                left: -half_size.x,
+                right: half_size.x,
+                bottom: -half_size.y,
+                top: half_size.y,
+                scaling_mode: ScalingMode::None,
+                ..Default::default()
+            },
+            ..camera_bundle
+        },
+        RenderLayers::layer(TRAIL_LAYER),
+        UiCameraConfig { show_ui: false },
+        TrailCamera,
+    ));
+
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(shape::Quad::new(size).into()).into(),
+            material: fade.clone(),
+            transform: Transform::from_translation(center.extend(0.0)),
+            ..Default::default()
+        },
+        RenderLayers::layer(TRAIL_LAYER),
+    ));
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: particles.clone().into(),
+            material: additive_materials.add(AdditiveMaterial {}),
+            // In front of the fade pass
+            transform: Transform::from_xyz(0.0, 0.0, 1.0),
+            ..Default::default()
+        },
+        RenderLayers::layer(TRAIL_LAYER),
+        // The mesh changes every frame, so its bounding box would go stale.
+        NoFrustumCulling,
+    ));
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(size),
+                ..Default::default()
+            },
+            texture: trail_images[0].clone(),
+            // Behind the particles and the lines
+            transform: Transform::from_translation(center.extend(-0.1)),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        },
+        TrailSprite,
+    ));
+
+    *textures = TrailTextures {
+        images: trail_images,
+        current: 0,
+        fade,
+        particles,
+    };
+}
+
+/// A black texture that can be drawn into and sampled.
+fn trail_image(width: u32, height: u32) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0; 8],
+        TextureFormat::Rgba16Float,
+    );
+    image.texture_descriptor.usage =
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+    image
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_trail_texture(
+    trail_buffer: Res<TrailBuffer>,
+    colors: Res<ParticleColors>,
+    mut textures: ResMut<TrailTextures>,
+    // Whether the texture was drawn on the previous frame
+    mut drawn: Local<bool>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut fade_materials: ResMut<Assets<FadeMaterial>>,
+    mut cameras: Query<&mut Camera, With<TrailCamera>>,
+    mut sprites: Query<(&mut Handle<Image>, &mut Visibility), With<TrailSprite>>,
+    query: Query<(&Position, &ColorId, &SimulationId)>,
+) {
+    let enabled = trail_buffer.enabled && trail_buffer.mode == TrailMode::Texture;
+    // Avoid triggering change detection every frame
+    for mut camera in &mut cameras {
+        if camera.is_active != enabled {
+            camera.is_active = enabled;
+        }
+    }
+    for (_, mut visibility) in &mut sprites {
+        if visibility.is_visible != enabled {
+            visibility.is_visible = enabled;
+        }
+    }
+    // Start afresh when enabled again
+    let decay = if *drawn { trail_buffer.decay } else { 0.0 };
+    *drawn = enabled;
+    if !enabled {
+        return;
+    }
+
+    textures.current = 1 - textures.current;
+    let (current, previous) = (
+        textures.images[textures.current].clone(),
+        textures.images[1 - textures.current].clone(),
+    );
+    for mut camera in &mut cameras {
+        camera.target = RenderTarget::Image(current.clone());
+    }
+    for (mut texture, _) in &mut sprites {
+        *texture = current.clone();
+    }
+    if let Some(fade) = fade_materials.get_mut(&textures.fade) {
+        *fade = FadeMaterial { decay, previous };
+    }
+
+    let (mut positions, mut vertex_colors) = (Vec::new(), Vec::new());
+    for (position, color, &simulation) in &query {
+        if simulation != SimulationId::MAIN {
+            continue;
+        }
+        let Some(color) = colors.0.get(color.0) else {
+            continue;
+        };
+        let center = single_vec2(position.0);
+        for corner in [
+            Vec2::new(-1.0, -1.0),
+            Vec2::new(1.0, -1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(-1.0, 1.0),
+        ] {
+            positions.push((center + PARTICLE_RADIUS * corner).extend(0.0).to_array());
+        }
+        // Opaque where bright, so that the trails fade into the background
+        let [r, g, b, _] = color.as_linear_rgba_f32();
+        vertex_colors.extend([[r, g, b, r.max(g).max(b)]; 4]);
+    }
+    // Avoid uploading empty vertex buffers: draw a single invisible, degenerate square instead.
+    if positions.is_empty() {
+        positions = vec![[0.0; 3]; 4];
+        vertex_colors = vec![[0.0; 4]; 4];
+    }
+    let indices = (0..positions.len() as u32 / 4)
+        .flat_map(|square| [0, 1, 2, 0, 2, 3].map(|corner| 4 * square + corner))
+        .collect();
+
+    let Some(mesh) = meshes.get_mut(&textures.particles) else {
+        return;
+    };
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vertex_colors);
+    mesh.set_indices(Some(Indices::U32(indices)));
+}
+
+fn update_geometry_trails(
+    mut commands: Commands,
+    trail_buffer: Res<TrailBuffer>,
+    bounds: Res<WorldBounds>,
+    colors: Res<ParticleColors>,
+    mut lines: ResMut<Lines>,
+    mut query: Query<(
+        Entity,
+        &Position,
+        &ColorId,
+        &SimulationId,
+        Option<&mut TrailHistory>,
+    )>,
+) {
+    let length = match trail_buffer.mode {
+        TrailMode::Geometry { length } if trail_buffer.enabled => length,
+        _ => {
+            // Start afresh when enabled again
+            if trail_buffer.is_changed() {
+                for (entity, .., history) in &query {
+                    if history.is_some() {
+                        commands.entity(entity).remove::<TrailHistory>();
+                    }
+                }
+            }
+            return;
+        }
+    };
+
+    let half_size = bounds.size() / 2.0;
+    for (entity, position, color, &simulation, history) in &mut query {
+        if simulation != SimulationId::MAIN {
+            continue;
+        }
+        let position = single_vec2(position.0);
+        let Some(mut history) = history else {
+            commands
+                .entity(entity)
+                .insert(TrailHistory(VecDeque::from([position])));
+            continue;
+        };
+        history.0.push_front(position);
+        history.0.truncate(length + 1);
+
+        let Some(&color) = colors.0.get(color.0) else {
+            continue;
+        };
+        let segments = history.0.iter().zip(history.0.iter().skip(1));
+        for (age, (&end, &start)) in segments.enumerate() {
+            // Particles crossing the edge of the world jump to the other side
+            if (end - start).abs().cmpgt(half_size).any() {
+                continue;
+            }
+            let mut faded = color;
+            faded.set_a(color.a() * trail_buffer.decay.powi(age as i32));
+            lines.line(start, end, faded);
+        }
+    }
+}
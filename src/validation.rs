@@ -0,0 +1,441 @@
+use std::{cmp::Ordering, error::Error, fmt};
+
+use crate::{
+    AdaptiveTimestep, AttractionRadius, ColorAttractions, ColorId, Particle, ParticleLifePlugin,
+    Reactions, SimulationId,
+};
+
+/// A single inconsistency in the settings of a [`ParticleLifePlugin`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigProblem {
+    /// The palette has no colors, so no particle can be drawn.
+    NoColors,
+    /// The attraction matrix of `simulation` has `rows` rows instead of one per color.
+    AttractionRows {
+        simulation: SimulationId,
+        colors: usize,
+        rows: usize,
+    },
+    /// The row `row` of the attraction matrix of `simulation` has `columns` entries instead of one
+    /// per color.
+    AttractionColumns {
+        simulation: SimulationId,
+        colors: usize,
+        row: usize,
+        columns: usize,
+    },
+    /// `count` initial particles of `simulation` have a color out of the palette, the first one
+    /// being the particle at `index` with the color `color`.
+    ColorOutOfRange {
+        simulation: SimulationId,
+        colors: usize,
+        index: usize,
+        color: ColorId,
+        count: usize,
+    },
+    /// The attraction radius of `simulation` doesn't have `rmin < rmax`.
+    InvalidAttractionRadius {
+        simulation: SimulationId,
+        radius: AttractionRadius,
+    },
+    /// The reaction at `index` in the [`Reactions`] has a reactant or product of color `color`,
+    /// out of the palette.
+    ReactionColorOutOfRange {
+        index: usize,
+        colors: usize,
+        color: ColorId,
+    },
+    /// The [`AdaptiveTimestep`] doesn't have `0 < min_dt <= max_dt` and `0 < max_displacement`.
+    InvalidAdaptiveTimestep(AdaptiveTimestep),
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoColors => write!(f, "no colors are given"),
+            Self::AttractionRows {
+                simulation,
+                colors,
+                rows,
+            } => write!(
+                f,
+                "the attraction matrix of simulation {} has {rows} rows for {colors} colors",
+                simulation.0
+            ),
+            Self::AttractionColumns {
+                simulation,
+                colors,
+                row,
+                columns,
+            } => write!(
+                f,
+                "row {row} of the attraction matrix of simulation {} has {columns} columns for \
+                 {colors} colors",
+                simulation.0
+            ),
+            Self::ColorOutOfRange {
+                simulation,
+                colors,
+                index,
+                color,
+                count,
+            } => write!(
+                f,
+                "{count} particles of simulation {} have a color out of the {colors} colors, \
+                 starting with particle {index} of color {}",
+                simulation.0, color.0
+            ),
+            Self::InvalidAttractionRadius { simulation, radius } => write!(
+                f,
+                "the attraction radius of simulation {} has rmin = {} not below rmax = {}",
+                simulation.0, radius.rmin, radius.rmax
+            ),
+            Self::ReactionColorOutOfRange {
+                index,
+                colors,
+                color,
+            } => write!(
+                f,
+                "reaction {index} involves color {} out of the {colors} colors",
+                color.0
+            ),
+            Self::InvalidAdaptiveTimestep(timestep) => write!(
+                f,
+                "the adaptive timestep has min_dt = {}, max_dt = {} and max_displacement = {}, \
+                 instead of 0 < min_dt <= max_dt and 0 < max_displacement",
+                timestep.min_dt, timestep.max_dt, timestep.max_displacement
+            ),
+        }
+    }
+}
+
+/// All the inconsistencies found by [`ParticleLifePlugin::validate`], in the order of the fields
+/// of the plugin and of the simulations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError(pub Vec<ConfigProblem>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, problem) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConfigError {}
+
+impl ParticleLifePlugin {
+    /// Checks that there are colors, that the attraction matrices have a row and a column per
+    /// color, that the initial particles have colors of the palette and that the attraction radii
+    /// have `rmin < rmax`, in the main simulation and in the extra ones, that the reactions only
+    /// involve colors of the palette and that the bounds of the adaptive timestep are ordered.
+    ///
+    /// The plugin panics with this error when added to an app, instead of failing later on an
+    /// out-of-bounds index.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let colors = self.colors.len();
+        let mut problems = Vec::new();
+        if colors == 0 {
+            problems.push(ConfigProblem::NoColors);
+        }
+
+        let main = (
+            SimulationId::MAIN,
+            &self.initial_particles,
+            Some(&self.color_attractions),
+            Some(self.attraction_radius),
+        );
+        let extras = self
+            .extra_simulations
+            .iter()
+            .zip(1..)
+            .map(|(simulation, id)| {
+                (
+                    SimulationId(id),
+                    &simulation.initial_particles,
+                    simulation.settings.color_attractions.as_ref(),
+                    simulation.settings.attraction_radius,
+                )
+            });
+        for (simulation, particles, color_attractions, attraction_radius) in
+            [main].into_iter().chain(extras)
+        {
+            if let Some(color_attractions) = color_attractions {
+                check_matrix(simulation, color_attractions, colors, &mut problems);
+            }
+            check_colors(simulation, particles, colors, &mut problems);
+            if let Some(radius) = attraction_radius {
+                check_radius(simulation, radius, &mut problems);
+            }
+        }
+        check_reactions(&self.reactions, colors, &mut problems);
+        if let Some(timestep) = self.adaptive_timestep {
+            check_adaptive_timestep(timestep, &mut problems);
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError(problems))
+        }
+    }
+}
+
+pub(crate) fn check_matrix(
+    simulation: SimulationId,
+    color_attractions: &ColorAttractions,
+    colors: usize,
+    problems: &mut Vec<ConfigProblem>,
+) {
+    let rows = color_attractions.0.len();
+    if rows != colors {
+        problems.push(ConfigProblem::AttractionRows {
+            simulation,
+            colors,
+            rows,
+        });
+    }
+    for (row, attractions) in color_attractions.0.iter().enumerate() {
+        if attractions.len() != colors {
+            problems.push(ConfigProblem::AttractionColumns {
+                simulation,
+                colors,
+                row,
+                columns: attractions.len(),
+            });
+        }
+    }
+}
+
+pub(crate) fn check_radius(
+    simulation: SimulationId,
+    radius: AttractionRadius,
+    problems: &mut Vec<ConfigProblem>,
+) {
+    // Also catches NaN radii
+    if radius.rmin.partial_cmp(&radius.rmax) != Some(Ordering::Less) {
+        problems.push(ConfigProblem::InvalidAttractionRadius { simulation, radius });
+    }
+}
+
+fn check_adaptive_timestep(timestep: AdaptiveTimestep, problems: &mut Vec<ConfigProblem>) {
+    // Also catches NaN bounds
+    let ordered = 0.0 < timestep.min_dt
+        && timestep.min_dt <= timestep.max_dt
+        && 0.0 < timestep.max_displacement;
+    if !ordered {
+        problems.push(ConfigProblem::InvalidAdaptiveTimestep(timestep));
+    }
+}
+
+pub(crate) fn check_reactions(
+    reactions: &Reactions,
+    colors: usize,
+    problems: &mut Vec<ConfigProblem>,
+) {
+    for (index, reaction) in reactions.reactions.iter().enumerate() {
+        let (reactants, products) = (reaction.reactants, reaction.products);
+        let involved = [reactants.0, reactants.1, products.0, products.1];
+        if let Some(&color) = involved.iter().find(|color| color.0 >= colors) {
+            problems.push(ConfigProblem::ReactionColorOutOfRange {
+                index,
+                colors,
+                color,
+            });
+        }
+    }
+}
+
+fn check_colors(
+    simulation: SimulationId,
+    particles: &[Particle],
+    colors: usize,
+    problems: &mut Vec<ConfigProblem>,
+) {
+    let mut out_of_range = particles
+        .iter()
+        .enumerate()
+        .filter(|(_, particle)| particle.color.0 >= colors);
+    if let Some((index, particle)) = out_of_range.next() {
+        problems.push(ConfigProblem::ColorOutOfRange {
+            simulation,
+            colors,
+            index,
+            color: particle.color,
+            count: 1 + out_of_range.count(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+    use crate::{Attraction, ExtraSimulation, Position, Reaction, SimulationSettings, Velocity};
+
+    /// A valid plugin with two colors.
+    fn plugin() -> ParticleLifePlugin {
+        ParticleLifePlugin {
+            colors: vec![Color::RED, Color::GREEN],
+            color_attractions: ColorAttractions(vec![vec![Attraction(0.0); 2]; 2]),
+            attraction_radius: AttractionRadius {
+                rmin: 0.1,
+                rmax: 0.3,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn particle(color: usize) -> Particle {
+        Particle {
+            position: Position::default(),
+            velocity: Velocity::default(),
+            color: ColorId(color),
+        }
+    }
+
+    fn message(plugin: ParticleLifePlugin) -> String {
+        plugin.validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn valid_plugin() {
+        assert_eq!(plugin().validate(), Ok(()));
+    }
+
+    #[test]
+    fn no_colors() {
+        let plugin = ParticleLifePlugin {
+            colors: Vec::new(),
+            color_attractions: ColorAttractions(Vec::new()),
+            ..plugin()
+        };
+        assert_eq!(message(plugin), "no colors are given");
+    }
+
+    #[test]
+    fn attraction_rows() {
+        let plugin = ParticleLifePlugin {
+            color_attractions: ColorAttractions(vec![vec![Attraction(0.0); 2]]),
+            ..plugin()
+        };
+        assert_eq!(
+            message(plugin),
+            "the attraction matrix of simulation 0 has 1 rows for 2 colors"
+        );
+    }
+
+    #[test]
+    fn attraction_columns() {
+        let plugin = ParticleLifePlugin {
+            color_attractions: ColorAttractions(vec![
+                vec![Attraction(0.0); 2],
+                vec![Attraction(0.0); 3],
+            ]),
+            ..plugin()
+        };
+        assert_eq!(
+            message(plugin),
+            "row 1 of the attraction matrix of simulation 0 has 3 columns for 2 colors"
+        );
+    }
+
+    #[test]
+    fn color_out_of_range() {
+        let plugin = ParticleLifePlugin {
+            initial_particles: vec![particle(0), particle(2), particle(1), particle(5)],
+            ..plugin()
+        };
+        assert_eq!(
+            message(plugin),
+            "2 particles of simulation 0 have a color out of the 2 colors, starting with particle \
+             1 of color 2"
+        );
+    }
+
+    #[test]
+    fn invalid_attraction_radius() {
+        let plugin = ParticleLifePlugin {
+            attraction_radius: AttractionRadius {
+                rmin: 0.3,
+                rmax: 0.3,
+            },
+            ..plugin()
+        };
+        assert_eq!(
+            message(plugin),
+            "the attraction radius of simulation 0 has rmin = 0.3 not below rmax = 0.3"
+        );
+    }
+
+    #[test]
+    fn reaction_color_out_of_range() {
+        let plugin = ParticleLifePlugin {
+            reactions: Reactions {
+                reactions: vec![
+                    Reaction {
+                        reactants: (ColorId(0), ColorId(1)),
+                        duration: 1.0,
+                        products: (ColorId(1), ColorId(0)),
+                    },
+                    Reaction {
+                        reactants: (ColorId(1), ColorId(1)),
+                        duration: 1.0,
+                        products: (ColorId(0), ColorId(3)),
+                    },
+                ],
+                ..Default::default()
+            },
+            ..plugin()
+        };
+        assert_eq!(
+            message(plugin),
+            "reaction 1 involves color 3 out of the 2 colors"
+        );
+    }
+
+    #[test]
+    fn invalid_adaptive_timestep() {
+        let plugin = ParticleLifePlugin {
+            adaptive_timestep: Some(AdaptiveTimestep {
+                min_dt: 0.1,
+                max_dt: 0.01,
+                max_displacement: 0.005,
+            }),
+            ..plugin()
+        };
+        assert_eq!(
+            message(plugin),
+            "the adaptive timestep has min_dt = 0.1, max_dt = 0.01 and max_displacement = 0.005, \
+             instead of 0 < min_dt <= max_dt and 0 < max_displacement"
+        );
+    }
+
+    #[test]
+    fn problems_are_aggregated_across_simulations() {
+        let plugin = ParticleLifePlugin {
+            extra_simulations: vec![ExtraSimulation {
+                initial_particles: vec![particle(2)],
+                settings: SimulationSettings {
+                    attraction_radius: Some(AttractionRadius {
+                        rmin: f32::NAN,
+                        rmax: 0.3,
+                    }),
+                    ..Default::default()
+                },
+            }],
+            ..plugin()
+        };
+        assert_eq!(
+            message(plugin),
+            "1 particles of simulation 1 have a color out of the 2 colors, starting with particle \
+             0 of color 2; the attraction radius of simulation 1 has rmin = NaN not below rmax = \
+             0.3"
+        );
+    }
+}